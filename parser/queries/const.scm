@@ -0,0 +1,5 @@
+(const_item
+  (visibility_modifier)? @visibility
+  name: (identifier) @name
+  type: (_) @type
+  value: (_)? @value) @item
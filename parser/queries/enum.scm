@@ -0,0 +1,4 @@
+(enum_item
+  (visibility_modifier)? @visibility
+  name: (type_identifier) @name
+  body: (enum_variant_list) @body) @item
@@ -0,0 +1,4 @@
+(extern_crate_declaration
+  (visibility_modifier)? @visibility
+  name: (identifier) @name
+  alias: (identifier)? @alias) @item
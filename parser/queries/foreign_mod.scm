@@ -0,0 +1,4 @@
+; Foreign blocks have no name; the ABI is kept as `abi`.
+(foreign_mod_item
+  (extern_modifier) @abi
+  body: (declaration_list)? @body) @item
@@ -0,0 +1,14 @@
+; Free functions and methods with a body, like `FunctionInfoExtractor`.
+(function_item
+  (visibility_modifier)? @visibility
+  name: (identifier) @name
+  parameters: (parameters) @params
+  return_type: (_)? @return_type
+  body: (block) @body) @item
+
+; Trait methods without a default body.
+(function_signature_item
+  (visibility_modifier)? @visibility
+  name: (identifier) @name
+  parameters: (parameters) @params
+  return_type: (_)? @return_type) @item
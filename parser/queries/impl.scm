@@ -0,0 +1,5 @@
+; `name` is the implementing type, `trait` the implemented trait if any.
+(impl_item
+  trait: (_)? @trait
+  type: (_) @name
+  body: (declaration_list)? @body) @item
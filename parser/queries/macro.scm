@@ -0,0 +1,3 @@
+(macro_invocation
+  macro: (_) @name
+  (token_tree) @body) @item
@@ -0,0 +1,2 @@
+(macro_definition
+  name: (identifier) @name) @item
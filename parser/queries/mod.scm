@@ -0,0 +1,4 @@
+(mod_item
+  (visibility_modifier)? @visibility
+  name: (identifier) @name
+  body: (declaration_list)? @body) @item
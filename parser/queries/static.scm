@@ -0,0 +1,6 @@
+(static_item
+  (visibility_modifier)? @visibility
+  (mutable_specifier)? @mut
+  name: (identifier) @name
+  type: (_) @type
+  value: (_)? @value) @item
@@ -0,0 +1,4 @@
+(struct_item
+  (visibility_modifier)? @visibility
+  name: (type_identifier) @name
+  body: (_)? @body) @item
@@ -0,0 +1,5 @@
+(trait_item
+  (visibility_modifier)? @visibility
+  name: (type_identifier) @name
+  bounds: (trait_bounds)? @supertraits
+  body: (declaration_list) @body) @item
@@ -0,0 +1,4 @@
+(type_item
+  (visibility_modifier)? @visibility
+  name: (type_identifier) @name
+  type: (_) @aliased_type) @item
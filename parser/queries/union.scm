@@ -0,0 +1,4 @@
+(union_item
+  (visibility_modifier)? @visibility
+  name: (type_identifier) @name
+  body: (field_declaration_list) @body) @item
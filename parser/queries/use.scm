@@ -0,0 +1,4 @@
+; Use declarations have no name; the imported path is kept as `path`.
+(use_declaration
+  (visibility_modifier)? @visibility
+  argument: (_) @path) @item
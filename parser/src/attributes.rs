@@ -0,0 +1,356 @@
+// src/attributes.rs
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+/// A parsed `#[...]` attribute.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttributeInfo {
+    /// Path of the attribute, e.g. `derive`, `cfg_attr` or `serde`
+    pub path: String,
+    /// The token tree after the path, e.g. `(Debug, Clone)`
+    pub arguments: Option<String>,
+    /// The expression after `=`, e.g. `"foo.rs"` for `#[path = "foo.rs"]`
+    pub value: Option<String>,
+    /// Macro names listed in `#[derive(...)]`, e.g. `["Debug", "serde::Serialize"]`
+    pub derives: Vec<String>,
+    /// The predicate of `#[cfg(...)]`, or the condition of `#[cfg_attr(...)]`
+    pub cfg: Option<CfgPredicate>,
+    /// The attributes `#[cfg_attr(condition, ...)]` applies when `cfg` holds
+    pub cfg_attributes: Vec<AttributeInfo>,
+    /// The attribute as written
+    pub text: String,
+    pub start_position: usize,
+    pub end_position: usize,
+}
+
+/// A `cfg` predicate, e.g. `all(unix, feature = "serde")`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CfgPredicate {
+    /// A bare option such as `test` or `unix`
+    Option(String),
+    /// `key = "value"`, e.g. `feature = "serde"`
+    KeyValue {
+        key: String,
+        value: String,
+    },
+    All(Vec<CfgPredicate>),
+    Any(Vec<CfgPredicate>),
+    Not(Box<CfgPredicate>),
+    /// Anything that is not valid `cfg` syntax, as written
+    Unknown(String),
+}
+
+/// Attributes directly above `node`, in source order. Doc comments between
+/// them are skipped.
+pub fn leading_attributes(node: Node, code: &str) -> Vec<AttributeInfo> {
+    let mut attributes = Vec::new();
+    let mut current = node.prev_sibling();
+    while let Some(sibling) = current {
+        match sibling.kind() {
+            "attribute_item" => attributes.extend(parse_attribute_item(sibling, code)),
+            "line_comment" | "block_comment" => {}
+            _ => break,
+        }
+        current = sibling.prev_sibling();
+    }
+    attributes.reverse();
+    attributes
+}
+
+/// The `#![...]` attributes at the top of `container`, which is a
+/// `source_file` or the `declaration_list` body of an inline module.
+pub fn inner_attributes(container: Node, code: &str) -> Vec<AttributeInfo> {
+    let mut attributes = Vec::new();
+    let mut cursor = container.walk();
+    for child in container.children(&mut cursor) {
+        match child.kind() {
+            "inner_attribute_item" => attributes.extend(parse_attribute_item(child, code)),
+            // The opening brace of a module body and a leading `#!` line.
+            "line_comment" | "block_comment" | "{" | "shebang" => {}
+            _ => break,
+        }
+    }
+    attributes
+}
+
+/// Every derive on an item, including those applied through `cfg_attr`.
+pub fn derive_names(attributes: &[AttributeInfo]) -> Vec<String> {
+    attributes
+        .iter()
+        .flat_map(|attribute| {
+            let mut names = attribute.derives.clone();
+            names.extend(derive_names(&attribute.cfg_attributes));
+            names
+        })
+        .collect()
+}
+
+/// Whether an attribute with the given path, e.g. `macro_export`, is present.
+pub fn has_attribute(attributes: &[AttributeInfo], path: &str) -> bool {
+    attributes.iter().any(|attribute| attribute.path == path)
+}
+
+/// Parses an `attribute_item` or `inner_attribute_item` node.
+pub fn parse_attribute_item(attribute_item: Node, code: &str) -> Option<AttributeInfo> {
+    let mut cursor = attribute_item.walk();
+    let attribute = attribute_item
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "attribute")?;
+    let mut path_cursor = attribute.walk();
+    let path = attribute.named_children(&mut path_cursor).next()?;
+    let arguments = attribute.child_by_field_name("arguments");
+    let mut attribute_info = AttributeInfo {
+        path: node_text(path, code),
+        arguments: arguments.map(|arguments| node_text(arguments, code)),
+        value: attribute
+            .child_by_field_name("value")
+            .map(|value| node_text(value, code)),
+        text: node_text(attribute_item, code),
+        start_position: attribute_item.start_byte(),
+        end_position: attribute_item.end_byte(),
+        ..Default::default()
+    };
+    if let Some(arguments) = arguments {
+        let groups = token_groups(arguments);
+        parse_arguments(&mut attribute_info, &groups, code);
+    }
+    Some(attribute_info)
+}
+
+/// Fills in the derive names and cfg predicate of well-known attributes from
+/// their comma separated arguments.
+fn parse_arguments(attribute_info: &mut AttributeInfo, groups: &[Vec<Node>], code: &str) {
+    match attribute_info.path.as_str() {
+        "derive" => {
+            attribute_info.derives = groups.iter().map(|group| group_text(group, code)).collect();
+        }
+        "cfg" => {
+            attribute_info.cfg = groups.first().map(|group| parse_cfg(group, code));
+        }
+        "cfg_attr" => {
+            let Some((condition, applied)) = groups.split_first() else {
+                return;
+            };
+            attribute_info.cfg = Some(parse_cfg(condition, code));
+            attribute_info.cfg_attributes = applied
+                .iter()
+                .filter_map(|group| parse_meta(group, code))
+                .collect();
+        }
+        _ => {}
+    }
+}
+
+/// Parses one attribute written inside `cfg_attr`, e.g. `derive(Serialize)`.
+fn parse_meta(group: &[Node], code: &str) -> Option<AttributeInfo> {
+    let (first, last) = (group.first()?, group.last()?);
+    let path_end = group
+        .iter()
+        .position(|token| token.kind() == "token_tree" || token.kind() == "=")
+        .unwrap_or(group.len());
+    let mut attribute_info = AttributeInfo {
+        path: group_text(&group[..path_end], code),
+        text: group_text(group, code),
+        start_position: first.start_byte(),
+        end_position: last.end_byte(),
+        ..Default::default()
+    };
+    match group.get(path_end) {
+        Some(arguments) if arguments.kind() == "token_tree" => {
+            attribute_info.arguments = Some(node_text(*arguments, code));
+            let groups = token_groups(*arguments);
+            parse_arguments(&mut attribute_info, &groups, code);
+        }
+        Some(_) => attribute_info.value = Some(group_text(&group[path_end + 1..], code)),
+        None => {}
+    }
+    Some(attribute_info)
+}
+
+fn parse_cfg(group: &[Node], code: &str) -> CfgPredicate {
+    match group {
+        [option] if option.kind() == "identifier" => CfgPredicate::Option(node_text(*option, code)),
+        [key, equals, value]
+            if key.kind() == "identifier"
+                && equals.kind() == "="
+                && value.kind() == "string_literal" =>
+        {
+            let value = node_text(*value, code);
+            CfgPredicate::KeyValue {
+                key: node_text(*key, code),
+                value: value.trim_matches('"').to_string(),
+            }
+        }
+        [operator, arguments] if arguments.kind() == "token_tree" => {
+            let predicates = token_groups(*arguments)
+                .iter()
+                .map(|group| parse_cfg(group, code))
+                .collect::<Vec<_>>();
+            match node_text(*operator, code).as_str() {
+                "all" => CfgPredicate::All(predicates),
+                "any" => CfgPredicate::Any(predicates),
+                "not" if predicates.len() == 1 => {
+                    CfgPredicate::Not(Box::new(predicates.into_iter().next().unwrap()))
+                }
+                _ => CfgPredicate::Unknown(group_text(group, code)),
+            }
+        }
+        _ => CfgPredicate::Unknown(group_text(group, code)),
+    }
+}
+
+/// Splits the tokens between the delimiters of a `token_tree` at its
+/// top-level commas.
+fn token_groups(token_tree: Node) -> Vec<Vec<Node>> {
+    let mut groups = Vec::new();
+    let mut group = Vec::new();
+    let mut cursor = token_tree.walk();
+    let children: Vec<Node> = token_tree.children(&mut cursor).collect();
+    // The first and last children are the delimiters.
+    for token in children
+        .iter()
+        .skip(1)
+        .take(children.len().saturating_sub(2))
+    {
+        if token.is_extra() {
+            continue;
+        }
+        if token.kind() == "," {
+            groups.push(std::mem::take(&mut group));
+        } else {
+            group.push(*token);
+        }
+    }
+    if !group.is_empty() {
+        groups.push(group);
+    }
+    groups.retain(|group| !group.is_empty());
+    groups
+}
+
+/// The source text from the first to the last token of a group.
+fn group_text(group: &[Node], code: &str) -> String {
+    match (group.first(), group.last()) {
+        (Some(first), Some(last)) => code[first.start_byte()..last.end_byte()].to_string(),
+        _ => String::new(),
+    }
+}
+
+fn node_text(node: Node, code: &str) -> String {
+    node.utf8_text(code.as_bytes())
+        .map(|text| text.to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tree_sitter::Parser;
+
+    /// Parses the attributes written above `fn f() {}`.
+    fn attributes_of(attributes: &str) -> Vec<AttributeInfo> {
+        let code = format!("{}\nfn f() {{}}\n", attributes);
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(&code, None).unwrap();
+        let root = tree.root_node();
+        let mut cursor = root.walk();
+        let function = root
+            .named_children(&mut cursor)
+            .find(|child| child.kind() == "function_item")
+            .unwrap();
+        leading_attributes(function, &code)
+    }
+
+    fn cfg_of(attribute: &str) -> CfgPredicate {
+        attributes_of(attribute)[0].cfg.clone().unwrap()
+    }
+
+    fn option(name: &str) -> CfgPredicate {
+        CfgPredicate::Option(name.to_string())
+    }
+
+    fn key_value(key: &str, value: &str) -> CfgPredicate {
+        CfgPredicate::KeyValue {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    #[test]
+    fn cfg_option_and_key_value() {
+        assert_eq!(cfg_of("#[cfg(test)]"), option("test"));
+        assert_eq!(
+            cfg_of(r#"#[cfg(feature = "serde")]"#),
+            key_value("feature", "serde")
+        );
+    }
+
+    #[test]
+    fn cfg_all_and_any() {
+        assert_eq!(
+            cfg_of(r#"#[cfg(all(unix, target_pointer_width = "64"))]"#),
+            CfgPredicate::All(vec![
+                option("unix"),
+                key_value("target_pointer_width", "64")
+            ])
+        );
+        assert_eq!(
+            cfg_of(r#"#[cfg(any(windows, all(unix, feature = "x"),))]"#),
+            CfgPredicate::Any(vec![
+                option("windows"),
+                CfgPredicate::All(vec![option("unix"), key_value("feature", "x")]),
+            ])
+        );
+        assert_eq!(cfg_of("#[cfg(all())]"), CfgPredicate::All(vec![]));
+    }
+
+    #[test]
+    fn cfg_nested_not() {
+        assert_eq!(
+            cfg_of("#[cfg(not(not(test)))]"),
+            CfgPredicate::Not(Box::new(CfgPredicate::Not(Box::new(option("test")))))
+        );
+        assert_eq!(
+            cfg_of(r#"#[cfg(all(not(feature = "std"), not(test)))]"#),
+            CfgPredicate::All(vec![
+                CfgPredicate::Not(Box::new(key_value("feature", "std"))),
+                CfgPredicate::Not(Box::new(option("test"))),
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_cfg_is_kept_as_written() {
+        assert_eq!(
+            cfg_of("#[cfg(not(a, b))]"),
+            CfgPredicate::Unknown("not(a, b)".to_string())
+        );
+        assert_eq!(
+            cfg_of("#[cfg(feature = 1)]"),
+            CfgPredicate::Unknown("feature = 1".to_string())
+        );
+    }
+
+    #[test]
+    fn cfg_attr_condition_and_applied_attributes() {
+        let attribute = attributes_of(
+            r#"#[cfg_attr(feature = "serde", derive(Serialize, Deserialize), serde(default))]"#,
+        )
+        .remove(0);
+        assert_eq!(attribute.cfg, Some(key_value("feature", "serde")));
+        let applied: Vec<&str> = attribute
+            .cfg_attributes
+            .iter()
+            .map(|applied| applied.path.as_str())
+            .collect();
+        assert_eq!(applied, ["derive", "serde"]);
+        assert_eq!(
+            derive_names(&[attribute]),
+            ["Serialize".to_string(), "Deserialize".to_string()]
+        );
+    }
+}
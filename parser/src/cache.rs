@@ -0,0 +1,255 @@
+// src/cache.rs
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::crates::CrateContext;
+use crate::extract::{ExtractedData, ExtractorKind};
+
+/// Items extracted from one file, stored with the hash of the contents they
+/// were extracted from.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub content_hash: String,
+    pub crate_context: CrateContext,
+    pub data: ExtractedData,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct CacheFile {
+    fingerprint: String,
+    entries: BTreeMap<String, CacheEntry>,
+}
+
+// Borrowed twin of `CacheFile` so saving does not clone every entry.
+#[derive(Serialize)]
+struct CacheFileRef<'a> {
+    fingerprint: &'a str,
+    entries: &'a BTreeMap<String, CacheEntry>,
+}
+
+/// Per-file extraction results carried over between indexing runs.
+///
+/// Entries from the previous run are only read; every file seen in this run
+/// is recorded into a fresh set of entries, so files that were deleted since
+/// the last run simply drop out when the cache is saved.
+#[derive(Debug, Default)]
+pub struct ExtractionCache {
+    fingerprint: String,
+    previous: BTreeMap<String, CacheEntry>,
+    current: BTreeMap<String, CacheEntry>,
+    pub files_reused: usize,
+    pub files_extracted: usize,
+}
+
+impl ExtractionCache {
+    /// Loads the cache saved at `path`. A missing or unreadable cache, or one
+    /// written with different settings, starts out empty.
+    pub fn load(path: &Path, fingerprint: String) -> Self {
+        let mut cache = ExtractionCache {
+            fingerprint,
+            ..Default::default()
+        };
+        if !path.exists() {
+            return cache;
+        }
+        let cache_file = fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|ron_string| Ok(ron::from_str::<CacheFile>(&ron_string)?));
+        match cache_file {
+            Ok(cache_file) if cache_file.fingerprint == cache.fingerprint => {
+                println!("Loaded extraction cache: {}", path.display());
+                cache.previous = cache_file.entries;
+            }
+            Ok(_) => println!(
+                "Extraction settings changed, ignoring cache: {}",
+                path.display()
+            ),
+            Err(e) => eprintln!("Failed to load cache '{}': {}", path.display(), e),
+        }
+        cache
+    }
+
+    /// An empty cache that never reuses anything, for runs with caching turned off.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Returns the cached items for a file if its contents and crate are unchanged.
+    pub fn lookup(
+        &self,
+        file_path: &str,
+        content_hash: &str,
+        crate_context: &CrateContext,
+    ) -> Option<&ExtractedData> {
+        self.previous
+            .get(file_path)
+            .filter(|entry| {
+                entry.content_hash == content_hash && entry.crate_context == *crate_context
+            })
+            .map(|entry| &entry.data)
+    }
+
+    pub fn record(&mut self, file_path: String, entry: CacheEntry, reused: bool) {
+        if reused {
+            self.files_reused += 1;
+        } else {
+            self.files_extracted += 1;
+        }
+        self.current.insert(file_path, entry);
+    }
+
+    /// Files that were cached by the previous run but not seen in this one.
+    pub fn files_removed(&self) -> usize {
+        self.previous
+            .keys()
+            .filter(|file_path| !self.current.contains_key(*file_path))
+            .count()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            if !parent.as_os_str().is_empty() {
+                fs::create_dir_all(parent)?;
+            }
+        }
+        let cache_file = CacheFileRef {
+            fingerprint: &self.fingerprint,
+            entries: &self.current,
+        };
+        let ron_string = ron::ser::to_string(&cache_file)?;
+        fs::write(path, ron_string)
+            .with_context(|| format!("Failed to write cache '{}'", path.display()))
+    }
+}
+
+/// Version of what the extractors produce. Bump it whenever a change alters
+/// the items extracted from unchanged source, so caches written before the
+/// change are not reused. Changes to the fields of the item types are picked
+/// up by `item_layout_hash` without a bump.
+const EXTRACTION_SCHEMA_VERSION: u32 = 7;
+
+/// Identifies the settings a cache was written with. Entries are only valid
+/// for the same parser and extraction schema version, the same item fields,
+/// the same set of extractors and the same query files.
+pub fn cache_fingerprint(extractors: &[ExtractorKind], queries_hash: &str) -> String {
+    fingerprint(EXTRACTION_SCHEMA_VERSION, extractors, queries_hash)
+}
+
+fn fingerprint(schema_version: u32, extractors: &[ExtractorKind], queries_hash: &str) -> String {
+    format!(
+        "{}:{}:{}:{:?}:{}",
+        env!("CARGO_PKG_VERSION"),
+        schema_version,
+        item_layout_hash(),
+        extractors,
+        queries_hash
+    )
+}
+
+/// Hash of the field names of every item kind, read from one default item of
+/// each. Types only nested in items, such as `FieldInfo`, are not covered.
+fn item_layout_hash() -> String {
+    let sample = ExtractedData {
+        structs: vec![Default::default()],
+        functions: vec![Default::default()],
+        type_aliases: vec![Default::default()],
+        impls: vec![Default::default()],
+        use_dependencies: vec![Default::default()],
+        mods: vec![Default::default()],
+        enums: vec![Default::default()],
+        macros: vec![Default::default()],
+        traits: vec![Default::default()],
+        consts: vec![Default::default()],
+        statics: vec![Default::default()],
+        macro_definitions: vec![Default::default()],
+        unions: vec![Default::default()],
+        extern_crates: vec![Default::default()],
+        foreign_mods: vec![Default::default()],
+        imports: vec![Default::default()],
+        query_items: vec![Default::default()],
+        ..Default::default()
+    };
+    content_hash(&ron::ser::to_string(&sample).unwrap_or_default())
+}
+
+pub fn content_hash(code: &str) -> String {
+    blake3::hash(code.as_bytes()).to_hex().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::StructInfo;
+
+    fn entry(code: &str, crate_context: &CrateContext) -> CacheEntry {
+        let mut data = ExtractedData::default();
+        data.structs.push(StructInfo {
+            name: "Cached".to_string(),
+            ..Default::default()
+        });
+        CacheEntry {
+            content_hash: content_hash(code),
+            crate_context: crate_context.clone(),
+            data,
+        }
+    }
+
+    /// Saves a cache holding `entry` for `lib.rs` under `fingerprint`.
+    fn save_cache(path: &Path, fingerprint: String, entry: CacheEntry) {
+        let mut cache = ExtractionCache::load(path, fingerprint);
+        cache.record("lib.rs".to_string(), entry, false);
+        cache.save(path).unwrap();
+    }
+
+    #[test]
+    fn unchanged_files_are_reused_and_changed_ones_are_not() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.ron");
+        let crate_context = CrateContext {
+            crate_name: "p".to_string(),
+            ..Default::default()
+        };
+        let fingerprint = cache_fingerprint(&ExtractorKind::all(), "");
+        save_cache(
+            &path,
+            fingerprint.clone(),
+            entry("struct Cached;", &crate_context),
+        );
+
+        let cache = ExtractionCache::load(&path, fingerprint);
+        let hit = cache.lookup("lib.rs", &content_hash("struct Cached;"), &crate_context);
+        assert_eq!(hit.unwrap().structs[0].name, "Cached");
+        let changed = content_hash("struct Changed;");
+        assert!(cache.lookup("lib.rs", &changed, &crate_context).is_none());
+        let moved = CrateContext::default();
+        let unchanged = content_hash("struct Cached;");
+        assert!(cache.lookup("lib.rs", &unchanged, &moved).is_none());
+        assert!(cache
+            .lookup("other.rs", &unchanged, &crate_context)
+            .is_none());
+    }
+
+    #[test]
+    fn caches_of_another_schema_version_are_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("cache.ron");
+        let crate_context = CrateContext::default();
+        let stale = fingerprint(EXTRACTION_SCHEMA_VERSION - 1, &ExtractorKind::all(), "");
+        save_cache(&path, stale, entry("struct Cached;", &crate_context));
+
+        let cache = ExtractionCache::load(&path, cache_fingerprint(&ExtractorKind::all(), ""));
+        let unchanged = content_hash("struct Cached;");
+        assert!(cache.lookup("lib.rs", &unchanged, &crate_context).is_none());
+        assert_eq!(cache.files_removed(), 0);
+    }
+
+    #[test]
+    fn fingerprints_differ_by_extractors_and_queries() {
+        let all = cache_fingerprint(&ExtractorKind::all(), "");
+        assert_eq!(all, cache_fingerprint(&ExtractorKind::all(), ""));
+        assert_ne!(all, cache_fingerprint(&ExtractorKind::all()[..1], ""));
+        assert_ne!(all, cache_fingerprint(&ExtractorKind::all(), "queries"));
+    }
+}
@@ -0,0 +1,236 @@
+// src/cli.rs
+use std::path::PathBuf;
+
+use anyhow::Result;
+use clap::{Args, Parser, Subcommand};
+
+use crate::config::{Config, OutputFormat};
+use crate::extract::ExtractorKind;
+
+pub const DEFAULT_TARGET_DIR: &str = "../example_traverse_target/src";
+pub const DEFAULT_OUTPUT_PATH: &str = "data/extracted_data.ron";
+pub const DEFAULT_CACHE_PATH: &str = "data/extraction_cache.ron";
+pub const DEFAULT_QUERY_DIR: &str = "queries";
+
+#[derive(Debug, Parser)]
+#[command(
+    name = "parser",
+    about = "Extract semantic chunks from Rust source code"
+)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Parse a target directory and save the extracted data
+    Index(IndexArgs),
+    /// Index once, then keep the saved output up to date as files change
+    Watch(WatchArgs),
+    /// Count the tree-sitter node kinds in the target and flag the ones no
+    /// extractor handles
+    Census(CensusArgs),
+    /// Run the hand-written extractors and the query extractors side by side
+    /// and report the items only one of them finds. Uses the `queries`
+    /// directory when no query is configured.
+    CompareQueries(IndexArgs),
+    /// Print the summary table for a saved output file
+    Stats {
+        /// Saved output file to read
+        #[arg(short, long, default_value = DEFAULT_OUTPUT_PATH)]
+        input: PathBuf,
+    },
+    /// Print the source of every extracted item with the given name
+    Show {
+        /// Item name to look up, e.g. `Paddle` or `move_paddle`
+        name: String,
+        /// Saved output file to read
+        #[arg(short, long, default_value = DEFAULT_OUTPUT_PATH)]
+        input: PathBuf,
+    },
+    /// Print the tree-sitter syntax tree of a single file
+    DumpTree {
+        /// Rust source file to parse
+        file: PathBuf,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct WatchArgs {
+    #[command(flatten)]
+    pub index: IndexArgs,
+    /// Milliseconds to wait for a burst of changes to settle before re-indexing
+    #[arg(long, default_value_t = 300)]
+    pub debounce_ms: u64,
+}
+
+#[derive(Debug, Args)]
+pub struct CensusArgs {
+    #[command(flatten)]
+    pub index: IndexArgs,
+    /// Also count anonymous nodes such as keywords and punctuation
+    #[arg(long)]
+    pub include_anonymous: bool,
+    /// Break the counts down per file
+    #[arg(long)]
+    pub by_file: bool,
+    /// Break the counts down by the kind of the parent node
+    #[arg(long)]
+    pub by_parent: bool,
+}
+
+/// Flags for `index`. Anything left unset falls back to the config file.
+#[derive(Debug, Args)]
+pub struct IndexArgs {
+    /// Root directories of the target projects
+    pub roots: Vec<PathBuf>,
+    /// Config file to load (defaults to `rag.toml` in the current directory)
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Directory names to skip while walking, comma separated
+    #[arg(short = 'I', long = "ignore", value_delimiter = ',')]
+    pub ignore: Option<Vec<String>>,
+    /// Glob pattern a file must match to be indexed (repeatable)
+    #[arg(long)]
+    pub include: Vec<String>,
+    /// Glob pattern for files to skip (repeatable)
+    #[arg(long)]
+    pub exclude: Vec<String>,
+    /// Walk files listed in `.gitignore`, `.ignore` and `.ragignore` too
+    #[arg(long)]
+    pub no_ignore_files: bool,
+    /// Index files marked `@generated`
+    #[arg(long)]
+    pub include_generated: bool,
+    /// Skip files larger than this many bytes
+    #[arg(long)]
+    pub max_file_size: Option<u64>,
+    /// Where to write the extracted data
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
+    /// Format of the output file
+    #[arg(short, long, value_enum)]
+    pub format: Option<OutputFormat>,
+    /// Extractors to run, comma separated
+    #[arg(short, long = "extractor", value_enum, value_delimiter = ',')]
+    pub extractors: Vec<ExtractorKind>,
+    /// Query file, or directory of `.scm` query files, to run as an extra
+    /// extractor (repeatable)
+    #[arg(long = "query")]
+    pub queries: Vec<PathBuf>,
+    /// Fail instead of saving when any file has a syntax error
+    #[arg(long)]
+    pub strict: bool,
+    /// Save even when files have syntax errors, overriding `strict` in the
+    /// config file
+    #[arg(long, conflicts_with = "strict")]
+    pub no_strict: bool,
+    /// Re-extract every file instead of reusing cached results
+    #[arg(long)]
+    pub no_cache: bool,
+    /// Where to keep cached per-file results
+    #[arg(long)]
+    pub cache_path: Option<PathBuf>,
+    /// Number of worker threads used for parsing
+    #[arg(short = 'j', long)]
+    pub threads: Option<usize>,
+    /// Chunk size limit in bytes, recorded in the output for the embedding
+    /// stage; the parser does not split items itself
+    #[arg(long)]
+    pub max_chunk_bytes: Option<usize>,
+    /// Record that doc comments should be left out of item chunks
+    #[arg(long)]
+    pub no_doc_comments: bool,
+}
+
+impl IndexArgs {
+    /// Loads the config file and applies every flag that was given on top of it.
+    pub fn into_config(self) -> Result<Config> {
+        let mut config = Config::load(self.config.as_deref())?;
+        if !self.roots.is_empty() {
+            config.roots = self.roots;
+        }
+        if let Some(ignore) = self.ignore {
+            config.ignore_directories = ignore;
+        }
+        if !self.include.is_empty() {
+            config.include = self.include;
+        }
+        if !self.exclude.is_empty() {
+            config.exclude = self.exclude;
+        }
+        if self.no_ignore_files {
+            config.respect_ignore_files = false;
+        }
+        if self.include_generated {
+            config.skip_generated = false;
+        }
+        if self.max_file_size.is_some() {
+            config.max_file_size = self.max_file_size;
+        }
+        if let Some(output) = self.output {
+            config.output.path = output;
+        }
+        if let Some(format) = self.format {
+            config.output.format = format;
+        }
+        if !self.extractors.is_empty() {
+            config.extractors = self.extractors;
+        }
+        if !self.queries.is_empty() {
+            config.queries = self.queries;
+        }
+        if self.strict {
+            config.strict = true;
+        }
+        if self.no_strict {
+            config.strict = false;
+        }
+        if self.no_cache {
+            config.cache.enabled = false;
+        }
+        if let Some(cache_path) = self.cache_path {
+            config.cache.path = cache_path;
+        }
+        if self.threads.is_some() {
+            config.threads = self.threads;
+        }
+        if self.max_chunk_bytes.is_some() {
+            config.chunking.max_chunk_bytes = self.max_chunk_bytes;
+        }
+        if self.no_doc_comments {
+            config.chunking.include_doc_comments = false;
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_config(args: &[&str]) -> Result<Config> {
+        let cli = Cli::try_parse_from(["parser", "index"].iter().chain(args))?;
+        match cli.command {
+            Command::Index(args) => args.into_config(),
+            command => panic!("parsed as {:?}", command),
+        }
+    }
+
+    #[test]
+    fn strict_from_the_config_file_can_be_turned_off() {
+        let dir = tempfile::tempdir().unwrap();
+        let config_path = dir.path().join("rag.toml");
+        std::fs::write(&config_path, "strict = true\n").unwrap();
+        let config_path = config_path.to_str().unwrap();
+
+        assert!(index_config(&["--config", config_path]).unwrap().strict);
+        assert!(
+            !index_config(&["--config", config_path, "--no-strict"])
+                .unwrap()
+                .strict
+        );
+        assert!(index_config(&["--strict", "--no-strict"]).is_err());
+    }
+}
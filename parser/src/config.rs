@@ -0,0 +1,165 @@
+// src/config.rs
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::cli::{DEFAULT_CACHE_PATH, DEFAULT_OUTPUT_PATH, DEFAULT_TARGET_DIR};
+use crate::extract::ExtractorKind;
+
+/// Config file picked up from the current directory when `--config` is not given.
+pub const DEFAULT_CONFIG_FILE: &str = "rag.toml";
+
+/// Settings for one indexing run.
+///
+/// Loaded from `rag.toml`, then overridden by command line flags. The effective
+/// config is saved alongside the extracted data so a run can be reproduced.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Root directories of the target projects
+    pub roots: Vec<PathBuf>,
+    /// Directory names skipped while walking, e.g. `examples`
+    pub ignore_directories: Vec<String>,
+    /// Glob patterns, relative to a root, a file must match to be indexed.
+    /// An empty list selects every `.rs` file.
+    pub include: Vec<String>,
+    /// Glob patterns, relative to a root, for files that are never indexed
+    pub exclude: Vec<String>,
+    /// Honor `.gitignore`, `.ignore` and `.ragignore` files while walking
+    pub respect_ignore_files: bool,
+    /// Skip files with an `@generated` marker near the top
+    pub skip_generated: bool,
+    /// Skip files larger than this many bytes
+    pub max_file_size: Option<u64>,
+    pub extractors: Vec<ExtractorKind>,
+    /// Tree-sitter query files, or directories of `.scm` files, run as extra
+    /// extractors next to `extractors`
+    pub queries: Vec<PathBuf>,
+    /// Fail the run when any indexed file has a syntax error
+    pub strict: bool,
+    /// Number of worker threads used for parsing (defaults to one per CPU).
+    /// Not saved with the output, which is the same at any thread count.
+    #[serde(skip_serializing)]
+    pub threads: Option<usize>,
+    pub output: OutputConfig,
+    pub cache: CacheConfig,
+    pub chunking: ChunkingConfig,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            roots: vec![PathBuf::from(DEFAULT_TARGET_DIR)],
+            ignore_directories: vec!["examples".to_string(), "assets".to_string()],
+            include: Vec::new(),
+            exclude: Vec::new(),
+            respect_ignore_files: true,
+            skip_generated: true,
+            max_file_size: None,
+            extractors: ExtractorKind::all(),
+            queries: Vec::new(),
+            strict: false,
+            threads: None,
+            output: OutputConfig::default(),
+            cache: CacheConfig::default(),
+            chunking: ChunkingConfig::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OutputConfig {
+    pub format: OutputFormat,
+    pub path: PathBuf,
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            format: OutputFormat::Ron,
+            path: PathBuf::from(DEFAULT_OUTPUT_PATH),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputFormat {
+    #[default]
+    Ron,
+    Json,
+}
+
+impl OutputFormat {
+    /// Guesses the format of a saved file from its extension, falling back to RON.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => OutputFormat::Json,
+            _ => OutputFormat::Ron,
+        }
+    }
+}
+
+/// Where per-file extraction results are kept between runs.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CacheConfig {
+    pub enabled: bool,
+    pub path: PathBuf,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            path: PathBuf::from(DEFAULT_CACHE_PATH),
+        }
+    }
+}
+
+/// Options for turning extracted items into embedding chunks.
+///
+/// The parser does not apply these. They are only recorded in the saved
+/// output, for whatever cuts the extracted items into chunks later.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ChunkingConfig {
+    /// Whether doc comments should be kept as part of an item's chunk
+    pub include_doc_comments: bool,
+    /// Items longer than this many bytes should be split into several chunks
+    pub max_chunk_bytes: Option<usize>,
+}
+
+impl Default for ChunkingConfig {
+    fn default() -> Self {
+        Self {
+            include_doc_comments: true,
+            max_chunk_bytes: None,
+        }
+    }
+}
+
+impl Config {
+    /// Loads the config from `path`, or from `rag.toml` in the current directory
+    /// when no path is given. A missing default file yields the default config.
+    pub fn load(path: Option<&Path>) -> Result<Config> {
+        let path = match path {
+            Some(path) => path,
+            None => {
+                let default_path = Path::new(DEFAULT_CONFIG_FILE);
+                if !default_path.exists() {
+                    return Ok(Config::default());
+                }
+                default_path
+            }
+        };
+        println!("Loading config: {}", path.display());
+        let toml_string = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file '{}'", path.display()))?;
+        toml::from_str(&toml_string)
+            .with_context(|| format!("Failed to parse config file '{}'", path.display()))
+    }
+}
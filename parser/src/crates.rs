@@ -0,0 +1,533 @@
+// src/crates.rs
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use globset::Glob;
+use ignore::WalkBuilder;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::reachable_files;
+
+const MANIFEST_FILE: &str = "Cargo.toml";
+
+/// Which kind of cargo target a file belongs to, based on where it sits
+/// inside its package.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TargetKind {
+    /// Anything under `src/`
+    Src,
+    /// Integration tests under `tests/`
+    Tests,
+    Examples,
+    Benches,
+    /// The package's build script, usually `build.rs`
+    BuildScript,
+    /// Not part of any known target, or not inside a package at all
+    #[default]
+    Unknown,
+}
+
+/// Crate information attached to every extracted item.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CrateContext {
+    /// Name of the crate's target with `-` replaced by `_`, as it is written
+    /// in paths: the package name for the library and `src/main.rs`, or e.g.
+    /// `other` for `src/bin/other.rs`. See `CrateInfo::target_name`.
+    pub crate_name: String,
+    /// Root file of the crate the item is compiled into, e.g. `src/lib.rs`
+    pub crate_root: String,
+    pub target_kind: TargetKind,
+}
+
+impl CrateContext {
+    /// Module path of `file_path` inside this crate, derived from where the
+    /// file sits next to the crate root: `src/ui/button.rs` and
+    /// `src/ui/button/mod.rs` both give `["ui", "button"]`, and the crate root
+    /// itself gives an empty path. `#[path]` attributes are not followed;
+    /// `resolve_module_tree` corrects the items of such files afterwards.
+    pub fn module_path(&self, file_path: &Path) -> Vec<String> {
+        let crate_root = Path::new(&self.crate_root);
+        let Some(root_dir) = crate_root.parent() else {
+            return Vec::new();
+        };
+        if self.crate_root.is_empty() || file_path == crate_root {
+            return Vec::new();
+        }
+        let Ok(relative_path) = file_path.strip_prefix(root_dir) else {
+            return Vec::new();
+        };
+        let mut segments: Vec<String> = relative_path
+            .with_extension("")
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        if segments.last().is_some_and(|last| last == "mod") {
+            segments.pop();
+        }
+        segments
+    }
+
+    /// `module_path` prefixed with the crate name, e.g. `breakout::ui::button`,
+    /// or with `crate` when the crate has no known name.
+    pub fn qualified_module_path(&self, file_path: &Path) -> String {
+        let mut path = match self.crate_name.as_str() {
+            "" => "crate".to_string(),
+            crate_name => crate_name.to_string(),
+        };
+        for segment in self.module_path(file_path) {
+            path = format!("{}::{}", path, segment);
+        }
+        path
+    }
+}
+
+/// A package found while reading the manifests of the target.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CrateInfo {
+    pub name: String,
+    pub manifest_path: String,
+    /// Manifest of the workspace that lists this package as a member
+    pub workspace_manifest_path: Option<String>,
+    pub lib_root: Option<String>,
+    /// `name` of the `[lib]` section, when it differs from the package name
+    #[serde(default)]
+    pub lib_name: Option<String>,
+    pub bin_roots: Vec<String>,
+    /// `name` of every `[[bin]]` section with a `path`, by root file
+    #[serde(default)]
+    pub bin_names: BTreeMap<String, String>,
+    pub build_script: Option<String>,
+}
+
+impl CrateInfo {
+    /// Name of the crate compiled from the target rooted at `crate_root`, as
+    /// it is written in paths: the package name for the library and
+    /// `src/main.rs`, the `[[bin]]` name for other declared binaries, and the
+    /// file or directory name for discovered targets such as
+    /// `src/bin/other.rs` or `tests/api/main.rs`.
+    pub fn target_name(&self, crate_root: &str) -> String {
+        let package_dir = Path::new(&self.manifest_path)
+            .parent()
+            .unwrap_or(Path::new(""));
+        let root = Path::new(crate_root);
+        let name = if self.lib_root.as_deref() == Some(crate_root) {
+            self.lib_name.as_deref().unwrap_or(&self.name)
+        } else if let Some(bin_name) = self.bin_names.get(crate_root) {
+            bin_name
+        } else if self.build_script.as_deref() == Some(crate_root) {
+            "build_script_build"
+        } else if root == package_dir.join("src/main.rs") {
+            &self.name
+        } else {
+            let name = match root.file_name() {
+                Some(file_name) if file_name == "main.rs" => {
+                    root.parent().and_then(Path::file_name)
+                }
+                _ => root.file_stem(),
+            };
+            name.and_then(|name| name.to_str()).unwrap_or(&self.name)
+        };
+        name.replace('-', "_")
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    package: Option<Package>,
+    workspace: Option<Workspace>,
+    lib: Option<TargetSpec>,
+    #[serde(default)]
+    bin: Vec<TargetSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Package {
+    name: String,
+    build: Option<toml::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Workspace {
+    #[serde(default)]
+    members: Vec<String>,
+    #[serde(default)]
+    exclude: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TargetSpec {
+    name: Option<String>,
+    path: Option<PathBuf>,
+}
+
+/// All packages that own files under an indexed root, keyed by package directory.
+#[derive(Debug, Default)]
+pub struct CrateIndex {
+    crates: BTreeMap<PathBuf, CrateInfo>,
+    /// Root of the target each file is a module of, for packages with more
+    /// than one target root. The library wins over binaries reaching the
+    /// same file.
+    module_roots: HashMap<PathBuf, PathBuf>,
+}
+
+impl CrateIndex {
+    /// Reads every manifest that can own files under `root_dir`: the nearest
+    /// manifest above it, all manifests below it, and the members of any
+    /// workspace among them.
+    pub fn discover(root_dir: &Path) -> Result<Self> {
+        let root_dir = root_dir
+            .canonicalize()
+            .with_context(|| format!("Failed to canonicalize path: {}", root_dir.display()))?;
+        let mut index = CrateIndex::default();
+
+        for ancestor in root_dir.ancestors() {
+            let manifest_path = ancestor.join(MANIFEST_FILE);
+            if manifest_path.is_file() {
+                index.load_manifest(&manifest_path, None)?;
+            }
+        }
+        for entry in WalkBuilder::new(&root_dir).require_git(false).build() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if entry.file_name() == MANIFEST_FILE && entry.depth() > 0 {
+                index.load_manifest(entry.path(), None)?;
+            }
+        }
+        for crate_info in index.crates.values() {
+            let target_roots: Vec<&String> = crate_info
+                .lib_root
+                .iter()
+                .chain(&crate_info.bin_roots)
+                .collect();
+            if target_roots.len() < 2 {
+                continue;
+            }
+            for target_root in target_roots {
+                for file_path in reachable_files(Path::new(target_root)) {
+                    index
+                        .module_roots
+                        .entry(file_path)
+                        .or_insert_with(|| PathBuf::from(target_root));
+                }
+            }
+        }
+        Ok(index)
+    }
+
+    pub fn crates(&self) -> impl Iterator<Item = &CrateInfo> {
+        self.crates.values()
+    }
+
+    fn load_manifest(&mut self, manifest_path: &Path, workspace: Option<&Path>) -> Result<()> {
+        let package_dir = manifest_path
+            .parent()
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        if let Some(existing) = self.crates.get_mut(&package_dir) {
+            if existing.workspace_manifest_path.is_none() {
+                existing.workspace_manifest_path = workspace.map(|p| p.display().to_string());
+            }
+            return Ok(());
+        }
+
+        let toml_string = std::fs::read_to_string(manifest_path)
+            .with_context(|| format!("Failed to read manifest '{}'", manifest_path.display()))?;
+        let manifest: Manifest = toml::from_str(&toml_string)
+            .with_context(|| format!("Failed to parse manifest '{}'", manifest_path.display()))?;
+
+        if let Some(package) = &manifest.package {
+            println!(
+                "Found crate {} at {}",
+                package.name,
+                manifest_path.display()
+            );
+            self.crates.insert(
+                package_dir.clone(),
+                crate_info(&package_dir, manifest_path, workspace, package, &manifest),
+            );
+        }
+
+        if let Some(workspace_table) = &manifest.workspace {
+            for member_dir in workspace_members(&package_dir, workspace_table)? {
+                let member_manifest = member_dir.join(MANIFEST_FILE);
+                if member_manifest.is_file() && member_dir != package_dir {
+                    self.load_manifest(&member_manifest, Some(manifest_path))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Finds the crate a source file belongs to and where it sits in that crate.
+    pub fn context_for(&self, file_path: &Path) -> CrateContext {
+        // The package directory with the longest matching prefix owns the file.
+        let Some((package_dir, crate_info)) = self
+            .crates
+            .iter()
+            .filter(|(dir, _)| file_path.starts_with(dir))
+            .max_by_key(|(dir, _)| dir.components().count())
+        else {
+            return CrateContext::default();
+        };
+
+        let relative_path = file_path.strip_prefix(package_dir).unwrap_or(file_path);
+        let is_build_script = crate_info
+            .build_script
+            .as_deref()
+            .is_some_and(|build| Path::new(build) == file_path);
+        let target_kind = if is_build_script {
+            TargetKind::BuildScript
+        } else {
+            match relative_path.components().next() {
+                Some(Component::Normal(dir)) if dir == "src" => TargetKind::Src,
+                Some(Component::Normal(dir)) if dir == "tests" => TargetKind::Tests,
+                Some(Component::Normal(dir)) if dir == "examples" => TargetKind::Examples,
+                Some(Component::Normal(dir)) if dir == "benches" => TargetKind::Benches,
+                _ => TargetKind::Unknown,
+            }
+        };
+
+        let crate_root = self
+            .module_roots
+            .get(file_path)
+            .cloned()
+            .or_else(|| crate_root_for(package_dir, crate_info, relative_path, target_kind))
+            .map(|root| root.display().to_string())
+            .unwrap_or_default();
+        let crate_name = match crate_root.as_str() {
+            "" => crate_info.name.replace('-', "_"),
+            crate_root => crate_info.target_name(crate_root),
+        };
+        CrateContext {
+            crate_name,
+            crate_root,
+            target_kind,
+        }
+    }
+}
+
+fn crate_info(
+    package_dir: &Path,
+    manifest_path: &Path,
+    workspace: Option<&Path>,
+    package: &Package,
+    manifest: &Manifest,
+) -> CrateInfo {
+    let existing = |path: PathBuf| path.is_file().then(|| path.display().to_string());
+
+    let lib_root = match manifest.lib.as_ref().and_then(|lib| lib.path.as_ref()) {
+        Some(path) => existing(package_dir.join(path)),
+        None => existing(package_dir.join("src/lib.rs")),
+    };
+    let mut bin_roots = Vec::new();
+    let mut bin_names = BTreeMap::new();
+    for bin in &manifest.bin {
+        let Some(bin_root) = bin
+            .path
+            .as_ref()
+            .and_then(|path| existing(package_dir.join(path)))
+        else {
+            continue;
+        };
+        if let Some(name) = &bin.name {
+            bin_names.insert(bin_root.clone(), name.clone());
+        }
+        bin_roots.push(bin_root);
+    }
+    if let Some(main_rs) = existing(package_dir.join("src/main.rs")) {
+        if !bin_roots.contains(&main_rs) {
+            bin_roots.insert(0, main_rs);
+        }
+    }
+    let build_script = match &package.build {
+        Some(toml::Value::String(path)) => existing(package_dir.join(path)),
+        Some(toml::Value::Boolean(false)) => None,
+        _ => existing(package_dir.join("build.rs")),
+    };
+
+    CrateInfo {
+        name: package.name.clone(),
+        manifest_path: manifest_path.display().to_string(),
+        workspace_manifest_path: workspace.map(|p| p.display().to_string()),
+        lib_root,
+        lib_name: manifest
+            .lib
+            .as_ref()
+            .and_then(|lib| lib.name.clone())
+            .filter(|name| *name != package.name),
+        bin_roots,
+        bin_names,
+        build_script,
+    }
+}
+
+/// Expands the `members` globs of a workspace into package directories.
+fn workspace_members(workspace_dir: &Path, workspace: &Workspace) -> Result<Vec<PathBuf>> {
+    let mut members = Vec::new();
+    for pattern in &workspace.members {
+        if !pattern.contains(['*', '?', '[']) {
+            members.push(workspace_dir.join(pattern));
+            continue;
+        }
+        let matcher = Glob::new(pattern)
+            .with_context(|| format!("Invalid workspace member glob '{}'", pattern))?
+            .compile_matcher();
+        for entry in WalkBuilder::new(workspace_dir).require_git(false).build() {
+            let Ok(entry) = entry else {
+                continue;
+            };
+            let relative = entry
+                .path()
+                .strip_prefix(workspace_dir)
+                .unwrap_or(entry.path());
+            if entry.file_type().is_some_and(|ft| ft.is_dir()) && matcher.is_match(relative) {
+                members.push(entry.path().to_path_buf());
+            }
+        }
+    }
+    members.retain(|member| {
+        !workspace
+            .exclude
+            .iter()
+            .any(|excluded| *member == workspace_dir.join(excluded))
+    });
+    Ok(members
+        .into_iter()
+        .filter_map(|member| member.canonicalize().ok())
+        .collect())
+}
+
+/// Picks the root file of the crate `relative_path` is compiled into, for
+/// files no target root of a multi-target package reaches.
+///
+/// This is a best guess from where the file sits: a file under `src/`
+/// belongs to the library if there is one, binaries in `src/bin/` and
+/// targets in `tests/`, `examples/` and `benches/` are their own roots.
+fn crate_root_for(
+    package_dir: &Path,
+    crate_info: &CrateInfo,
+    relative_path: &Path,
+    target_kind: TargetKind,
+) -> Option<PathBuf> {
+    let absolute_path = package_dir.join(relative_path);
+    match target_kind {
+        TargetKind::BuildScript => Some(absolute_path),
+        TargetKind::Src => {
+            if relative_path.starts_with("src/bin") {
+                return Some(target_root(&package_dir.join("src/bin"), &absolute_path));
+            }
+            let file_path = absolute_path.display().to_string();
+            if crate_info.bin_roots.contains(&file_path) {
+                return Some(absolute_path);
+            }
+            crate_info
+                .lib_root
+                .as_ref()
+                .or(crate_info.bin_roots.first())
+                .map(PathBuf::from)
+        }
+        TargetKind::Tests | TargetKind::Examples | TargetKind::Benches => {
+            let target_dir = package_dir.join(relative_path.components().next()?);
+            Some(target_root(&target_dir, &absolute_path))
+        }
+        TargetKind::Unknown => None,
+    }
+}
+
+/// Root of an auto-discovered target such as `tests/foo.rs` or `tests/foo/main.rs`.
+fn target_root(target_dir: &Path, file_path: &Path) -> PathBuf {
+    let Ok(inside_target_dir) = file_path.strip_prefix(target_dir) else {
+        return file_path.to_path_buf();
+    };
+    let mut components = inside_target_dir.components();
+    match (components.next(), components.next()) {
+        (Some(Component::Normal(name)), Some(_)) => target_dir.join(name).join("main.rs"),
+        _ => file_path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_files(dir: &Path, files: &[(&str, &str)]) {
+        for (file_path, contents) in files {
+            let file_path = dir.join(file_path);
+            std::fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            std::fs::write(file_path, contents).unwrap();
+        }
+    }
+
+    #[test]
+    fn src_files_belong_to_the_target_that_declares_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let package_dir = dir.path().canonicalize().unwrap();
+        write_files(
+            &package_dir,
+            &[
+                ("Cargo.toml", "[package]\nname = \"my-pkg\"\n"),
+                ("src/lib.rs", "pub mod shared;\n"),
+                ("src/shared.rs", "pub fn helper() {}\n"),
+                ("src/main.rs", "mod cli;\nfn main() {}\n"),
+                ("src/cli.rs", "mod args;\n"),
+                ("src/cli/args.rs", ""),
+                ("src/stray.rs", ""),
+            ],
+        );
+        let index = CrateIndex::discover(&package_dir).unwrap();
+        let crate_root = |file_path: &str| {
+            let context = index.context_for(&package_dir.join(file_path));
+            assert_eq!(context.target_kind, TargetKind::Src);
+            context.crate_root
+        };
+        let lib_root = package_dir.join("src/lib.rs").display().to_string();
+        let main_root = package_dir.join("src/main.rs").display().to_string();
+
+        assert_eq!(crate_root("src/lib.rs"), lib_root);
+        assert_eq!(crate_root("src/shared.rs"), lib_root);
+        assert_eq!(crate_root("src/main.rs"), main_root);
+        assert_eq!(crate_root("src/cli.rs"), main_root);
+        assert_eq!(crate_root("src/cli/args.rs"), main_root);
+        // Reached from neither root, so the library is still the best guess.
+        assert_eq!(crate_root("src/stray.rs"), lib_root);
+    }
+
+    #[test]
+    fn crates_are_named_after_their_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let package_dir = dir.path().canonicalize().unwrap();
+        write_files(
+            &package_dir,
+            &[
+                (
+                    "Cargo.toml",
+                    "[package]\nname = \"my-pkg\"\n\n[[bin]]\nname = \"tool\"\npath = \"tools/run.rs\"\n",
+                ),
+                ("src/lib.rs", ""),
+                ("src/main.rs", "fn main() {}\n"),
+                ("src/bin/other.rs", "fn main() {}\n"),
+                ("tools/run.rs", "fn main() {}\n"),
+                ("tests/t.rs", ""),
+                ("tests/api/main.rs", "mod helpers;\n"),
+                ("tests/api/helpers.rs", ""),
+                ("build.rs", "fn main() {}\n"),
+            ],
+        );
+        let index = CrateIndex::discover(&package_dir).unwrap();
+        let crate_name =
+            |file_path: &str| index.context_for(&package_dir.join(file_path)).crate_name;
+
+        assert_eq!(crate_name("src/lib.rs"), "my_pkg");
+        assert_eq!(crate_name("src/main.rs"), "my_pkg");
+        assert_eq!(crate_name("src/bin/other.rs"), "other");
+        assert_eq!(crate_name("tools/run.rs"), "tool");
+        assert_eq!(crate_name("tests/t.rs"), "t");
+        assert_eq!(crate_name("tests/api/helpers.rs"), "api");
+        assert_eq!(crate_name("build.rs"), "build_script_build");
+    }
+}
@@ -4,7 +4,6 @@ use crate::Any;
 use std::any::type_name_of_val;
 use tree_sitter::Node;
 
-#[allow(dead_code)]
 // Recursive function to print the syntax tree
 pub fn print_syntax_tree(node: Node, source_code: &str, indent: usize) {
     let node_kind = node.kind();
@@ -31,7 +30,7 @@ pub fn print_syntax_tree(node: Node, source_code: &str, indent: usize) {
 pub(crate) fn process_any_debug(boxed_any: &Box<dyn Any>) {
     println!("Type inside Box<dyn Any>: {}", type_name_of_val(boxed_any));
 
-    if let Some(impl_info) = (&*boxed_any).downcast_ref::<ImplInfo>() {
+    if let Some(impl_info) = boxed_any.downcast_ref::<ImplInfo>() {
         println!("Found ImplInfo: {:?}", impl_info);
     } else {
         println!("Unknown type inside Box<dyn Any>");
@@ -43,7 +42,7 @@ pub fn process_box_take_ownership(boxed_any: Box<dyn Any>) {
     let none: Option<ImplInfo> = None;
     println!(
         "-- Type process_box_take_ownership: {:?}",
-        boxed_any.type_id()
+        (*boxed_any).type_id()
     );
     println!("-- Type None id: {:?}", none.type_id());
     match boxed_any.downcast::<ImplInfo>() {
@@ -0,0 +1,241 @@
+// src/diagnostics.rs
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+use crate::extract::ExtractedData;
+
+/// Longest piece of unexpected source text kept in a diagnostic message.
+const MAX_ERROR_TEXT_LEN: usize = 40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SyntaxErrorKind {
+    /// Source text the grammar could not make sense of (an `ERROR` node)
+    Error,
+    /// A token the parser inserted to recover, e.g. a missing `;`
+    Missing,
+    /// The parser returned no tree at all
+    ParseFailed,
+}
+
+/// A syntax error found in a parsed file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SyntaxDiagnostic {
+    pub kind: SyntaxErrorKind,
+    pub message: String,
+    /// 1-based line of the start of the error
+    pub line: usize,
+    /// 1-based column, in bytes, of the start of the error
+    pub column: usize,
+    pub start_position: usize,
+    pub end_position: usize,
+    /// The source line the error starts on
+    pub source_line: String,
+    pub file_path: String,
+}
+
+impl SyntaxDiagnostic {
+    fn new(
+        kind: SyntaxErrorKind,
+        message: String,
+        node: Node,
+        code: &str,
+        file_path: &str,
+    ) -> Self {
+        let position = node.start_position();
+        SyntaxDiagnostic {
+            kind,
+            message,
+            line: position.row + 1,
+            column: position.column + 1,
+            start_position: node.start_byte(),
+            end_position: node.end_byte(),
+            source_line: code
+                .lines()
+                .nth(position.row)
+                .unwrap_or_default()
+                .trim_end()
+                .to_string(),
+            file_path: file_path.to_string(),
+        }
+    }
+
+    /// A file the parser gave up on entirely. The whole file is the error region.
+    pub fn parse_failed(code: &str, file_path: &str) -> Self {
+        SyntaxDiagnostic {
+            kind: SyntaxErrorKind::ParseFailed,
+            message: "parsing failed".to_string(),
+            line: 1,
+            column: 1,
+            start_position: 0,
+            end_position: code.len(),
+            source_line: code.lines().next().unwrap_or_default().to_string(),
+            file_path: file_path.to_string(),
+        }
+    }
+
+    pub fn overlaps(&self, start_position: usize, end_position: usize) -> bool {
+        self.start_position < end_position && start_position < self.end_position
+            // A zero-width MISSING node still counts when it sits inside the
+            // item, or at its end where a missing `;` or `}` goes.
+            || (self.start_position == self.end_position
+                && (start_position..=end_position).contains(&self.start_position))
+    }
+}
+
+/// Collects every `ERROR` and `MISSING` node under `root`. Subtrees without
+/// errors are skipped, so a clean file costs a single `has_error` check.
+pub fn collect_syntax_diagnostics(
+    root: Node,
+    code: &str,
+    file_path: &str,
+) -> Vec<SyntaxDiagnostic> {
+    let mut diagnostics = Vec::new();
+    if root.has_error() {
+        collect_errors(root, code, file_path, &mut diagnostics);
+    }
+    diagnostics
+}
+
+fn collect_errors(
+    node: Node,
+    code: &str,
+    file_path: &str,
+    diagnostics: &mut Vec<SyntaxDiagnostic>,
+) {
+    if node.is_error() {
+        let text = node.utf8_text(code.as_bytes()).unwrap_or_default();
+        let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+        let message = if text.chars().count() > MAX_ERROR_TEXT_LEN {
+            let shortened: String = text.chars().take(MAX_ERROR_TEXT_LEN).collect();
+            format!("unexpected `{}...`", shortened)
+        } else {
+            format!("unexpected `{}`", text)
+        };
+        diagnostics.push(SyntaxDiagnostic::new(
+            SyntaxErrorKind::Error,
+            message,
+            node,
+            code,
+            file_path,
+        ));
+        // Errors nested inside an ERROR node only repeat the outer one.
+        return;
+    }
+    if node.is_missing() {
+        let message = format!("missing `{}`", node.kind());
+        diagnostics.push(SyntaxDiagnostic::new(
+            SyntaxErrorKind::Missing,
+            message,
+            node,
+            code,
+            file_path,
+        ));
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.has_error() {
+            collect_errors(child, code, file_path, diagnostics);
+        }
+    }
+}
+
+/// Flags every extracted item whose span overlaps one of the diagnostics of
+/// `data`, since the parser may have cut such items short or glued them together.
+pub fn mark_items_in_error_regions(data: &mut ExtractedData) {
+    let diagnostics = std::mem::take(&mut data.diagnostics);
+    for item in data.items_mut() {
+        let meta = item.meta_mut();
+        meta.in_error_region = diagnostics
+            .iter()
+            .any(|d| d.overlaps(meta.start_position, meta.end_position));
+    }
+    data.diagnostics = diagnostics;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crates::CrateContext;
+    use crate::extract::ExtractorKind;
+    use crate::traverse::{extract_file, ExtractContext, ExtractorTable, InfoExtractor};
+    use std::path::Path;
+
+    /// Extracts `code` with every extractor, as the file `/p/src/lib.rs`.
+    fn extract(code: &str) -> ExtractedData {
+        let boxed_extractors: Vec<Box<dyn InfoExtractor>> = ExtractorKind::all()
+            .iter()
+            .map(|kind| kind.extractor())
+            .collect();
+        let extractors: Vec<&dyn InfoExtractor> =
+            boxed_extractors.iter().map(|e| e.as_ref()).collect();
+        let path = Path::new("/p/src/lib.rs");
+        let context = ExtractContext::new(path, CrateContext::default());
+        extract_file(
+            path,
+            code.to_string(),
+            &context,
+            &ExtractorTable::new(&extractors),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn clean_files_have_no_diagnostics() {
+        let data = extract("fn f() -> u8 { 1 }\nstruct S;\n");
+        assert!(data.diagnostics.is_empty());
+        assert!(data.items().all(|item| !item.meta().in_error_region));
+    }
+
+    #[test]
+    fn error_nodes_become_diagnostics() {
+        let code = "struct Fine;\n\nfn broken() { let = ; }\n";
+        let data = extract(code);
+        let errors: Vec<_> = data
+            .diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.kind == SyntaxErrorKind::Error)
+            .collect();
+        assert!(!errors.is_empty(), "{:?}", data.diagnostics);
+        let error = errors[0];
+        assert_eq!(error.line, 3);
+        assert_eq!(error.source_line, "fn broken() { let = ; }");
+        assert_eq!(error.file_path, "/p/src/lib.rs");
+        assert!(error.message.starts_with("unexpected `"));
+
+        let broken = data.functions.iter().find(|f| f.name == "broken").unwrap();
+        assert!(broken.meta.in_error_region);
+        assert!(!data.structs[0].meta.in_error_region);
+    }
+
+    #[test]
+    fn missing_nodes_become_diagnostics() {
+        let data = extract("struct Point { x: u8 }\nconst C: u8 = 1\n");
+        let missing = data
+            .diagnostics
+            .iter()
+            .find(|diagnostic| diagnostic.kind == SyntaxErrorKind::Missing)
+            .unwrap_or_else(|| panic!("{:?}", data.diagnostics));
+        assert_eq!(missing.message, "missing `;`");
+        assert_eq!(missing.start_position, missing.end_position);
+        assert_eq!(missing.line, 2);
+        assert!(data.consts[0].meta.in_error_region);
+        assert!(!data.structs[0].meta.in_error_region);
+    }
+
+    #[test]
+    fn zero_width_errors_overlap_the_items_they_end_or_sit_in() {
+        let diagnostic = SyntaxDiagnostic::parse_failed("0123456789", "lib.rs");
+        assert!(diagnostic.overlaps(9, 12));
+        assert!(!diagnostic.overlaps(10, 12));
+        let missing = SyntaxDiagnostic {
+            start_position: 5,
+            end_position: 5,
+            ..diagnostic
+        };
+        assert!(missing.overlaps(0, 10));
+        assert!(missing.overlaps(0, 5));
+        assert!(!missing.overlaps(0, 4));
+        assert!(!missing.overlaps(6, 10));
+    }
+}
@@ -0,0 +1,292 @@
+// src/docs.rs
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+/// Documentation attached to an item, from `///` and `/** */` comments,
+/// `#[doc = "..."]` attributes, or their inner `//!`, `/*! */` and
+/// `#![doc = "..."]` forms.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocComment {
+    /// The documentation with comment markers and quotes stripped, one
+    /// source line per line
+    pub text: String,
+    /// Span from the first to the last piece of documentation. Attributes
+    /// and plain comments between doc comments fall inside it.
+    pub start_position: usize,
+    pub end_position: usize,
+}
+
+/// Which side of the documented item a doc comment is written on.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DocPlacement {
+    /// `///`, `/** */` and `#[doc]`, written before the item
+    Outer,
+    /// `//!`, `/*! */` and `#![doc]`, written inside the module or file
+    Inner,
+}
+
+/// The outer docs of `node`, read from the comments and attributes directly
+/// above it.
+pub fn outer_doc_comment(node: Node, code: &str) -> Option<DocComment> {
+    let mut pieces = Vec::new();
+    let mut current = node.prev_sibling();
+    while let Some(sibling) = current {
+        match sibling.kind() {
+            "attribute_item" | "line_comment" | "block_comment" => {
+                if let Some(text) = doc_text(sibling, code, DocPlacement::Outer) {
+                    pieces.push((sibling, text));
+                }
+            }
+            _ => break,
+        }
+        current = sibling.prev_sibling();
+    }
+    pieces.reverse();
+    join_pieces(pieces)
+}
+
+/// The inner docs at the top of `container`, which is a `source_file` or the
+/// `declaration_list` body of an inline module.
+pub fn inner_doc_comment(container: Node, code: &str) -> Option<DocComment> {
+    let mut pieces = Vec::new();
+    let mut cursor = container.walk();
+    for child in container.children(&mut cursor) {
+        match child.kind() {
+            "inner_attribute_item" | "line_comment" | "block_comment" => {
+                if let Some(text) = doc_text(child, code, DocPlacement::Inner) {
+                    pieces.push((child, text));
+                }
+            }
+            // The opening brace of a module body and a leading `#!` line.
+            "{" | "shebang" => {}
+            _ => break,
+        }
+    }
+    join_pieces(pieces)
+}
+
+fn join_pieces(pieces: Vec<(Node, String)>) -> Option<DocComment> {
+    let (first, _) = pieces.first()?;
+    let (last, _) = pieces.last()?;
+    let (start_position, end_position) = (first.start_byte(), last.end_byte());
+    let text = pieces
+        .into_iter()
+        .map(|(_, text)| text)
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(DocComment {
+        text,
+        start_position,
+        end_position,
+    })
+}
+
+/// The stripped text of a comment or attribute, if it is documentation
+/// written on the `placement` side.
+fn doc_text(node: Node, code: &str, placement: DocPlacement) -> Option<String> {
+    match node.kind() {
+        "line_comment" | "block_comment" => {
+            let marker = match placement {
+                DocPlacement::Outer => "outer",
+                DocPlacement::Inner => "inner",
+            };
+            node.child_by_field_name(marker)?;
+            let doc = node
+                .child_by_field_name("doc")?
+                .utf8_text(code.as_bytes())
+                .ok()?;
+            if node.kind() == "line_comment" {
+                Some(strip_leading_space(doc.trim_end_matches(['\r', '\n'])).to_string())
+            } else {
+                Some(strip_block_doc(doc))
+            }
+        }
+        "attribute_item" if placement == DocPlacement::Outer => doc_attribute_text(node, code),
+        "inner_attribute_item" if placement == DocPlacement::Inner => {
+            doc_attribute_text(node, code)
+        }
+        _ => None,
+    }
+}
+
+/// The string of a `#[doc = "..."]` attribute. Other attributes, including
+/// `#[doc(hidden)]`, have no doc text.
+fn doc_attribute_text(attribute_item: Node, code: &str) -> Option<String> {
+    let mut cursor = attribute_item.walk();
+    let attribute = attribute_item
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "attribute")?;
+    let mut path_cursor = attribute.walk();
+    let path = attribute.named_children(&mut path_cursor).next()?;
+    if path.utf8_text(code.as_bytes()).ok()? != "doc" {
+        return None;
+    }
+    let value = attribute.child_by_field_name("value")?;
+    let literal = value.utf8_text(code.as_bytes()).ok()?;
+    match value.kind() {
+        "string_literal" => Some(unescape(strip_quotes(literal))),
+        "raw_string_literal" => {
+            Some(strip_quotes(literal.trim_start_matches('r').trim_matches('#')).to_string())
+        }
+        // e.g. `#[doc = include_str!("README.md")]`, kept as written
+        _ => Some(literal.to_string()),
+    }
+}
+
+fn strip_quotes(literal: &str) -> &str {
+    let literal = literal.strip_prefix('"').unwrap_or(literal);
+    literal.strip_suffix('"').unwrap_or(literal)
+}
+
+/// Resolves the escapes that show up in doc strings. Anything unusual is
+/// kept as written.
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some('t') => unescaped.push('\t'),
+            Some('"') => unescaped.push('"'),
+            Some('\\') => unescaped.push('\\'),
+            Some('\'') => unescaped.push('\''),
+            // A line continuation skips the newline and the indentation after it.
+            Some('\n') => {
+                while chars.as_str().starts_with(char::is_whitespace) {
+                    chars.next();
+                }
+            }
+            Some(other) => {
+                unescaped.push('\\');
+                unescaped.push(other);
+            }
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// `/// text` is conventionally written with one space after the marker.
+fn strip_leading_space(line: &str) -> &str {
+    line.strip_prefix(' ').unwrap_or(line)
+}
+
+/// The lines of a `/** */` or `/*! */` comment without the leading ` * `
+/// decoration rustdoc also removes, and without blank first and last lines.
+fn strip_block_doc(doc: &str) -> String {
+    let lines: Vec<&str> = doc.lines().collect();
+    let decorated = lines
+        .iter()
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .all(|line| line.trim_start().starts_with('*'));
+    let mut stripped: Vec<&str> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let line = line.trim_end();
+            if i > 0 && decorated {
+                strip_leading_space(line.trim_start().trim_start_matches('*'))
+            } else if i > 0 {
+                line.trim_start()
+            } else {
+                strip_leading_space(line)
+            }
+        })
+        .collect();
+    while stripped.first().is_some_and(|line| line.trim().is_empty()) {
+        stripped.remove(0);
+    }
+    while stripped.last().is_some_and(|line| line.trim().is_empty()) {
+        stripped.pop();
+    }
+    stripped.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traverse::with_rust_parser;
+    use tree_sitter::Tree;
+
+    fn parse(code: &str) -> Tree {
+        with_rust_parser(|parser| Ok(parser.parse(code, None)))
+            .unwrap()
+            .unwrap()
+    }
+
+    /// The last item of the file `code`.
+    fn last_item(tree: &Tree) -> Node<'_> {
+        let root = tree.root_node();
+        root.named_child(root.named_child_count() - 1).unwrap()
+    }
+
+    #[test]
+    fn outer_docs_join_line_block_and_attribute_docs() {
+        let code = r#"// not documentation
+/// First line
+///   indented
+/**
+ * Block line
+ *   more
+ */
+#[doc = "Quoted \"text\"\tand \\ more"]
+#[doc(hidden)]
+#[derive(Debug)]
+struct S;
+"#;
+        let tree = parse(code);
+        let doc = outer_doc_comment(last_item(&tree), code).unwrap();
+        assert_eq!(
+            doc.text,
+            "First line\n  indented\nBlock line\n  more\nQuoted \"text\"\tand \\ more"
+        );
+        assert_eq!(doc.start_position, code.find("/// First").unwrap());
+        assert_eq!(doc.end_position, code.find("\n#[doc(hidden)]").unwrap());
+    }
+
+    #[test]
+    fn inner_and_outer_docs_are_kept_apart() {
+        let code = r#"//! Crate docs
+#![doc = r"raw \n"]
+//! last
+
+/// Docs of f
+fn f() {}
+"#;
+        let tree = parse(code);
+        let inner = inner_doc_comment(tree.root_node(), code).unwrap();
+        assert_eq!(inner.text, "Crate docs\nraw \\n\nlast");
+        let outer = outer_doc_comment(last_item(&tree), code).unwrap();
+        assert_eq!(outer.text, "Docs of f");
+    }
+
+    #[test]
+    fn items_without_docs_have_none() {
+        let code = "// plain\n/* block */\n#[inline]\nfn f() {}\n";
+        let tree = parse(code);
+        assert_eq!(outer_doc_comment(last_item(&tree), code), None);
+        assert_eq!(inner_doc_comment(tree.root_node(), code), None);
+    }
+
+    #[test]
+    fn unescape_resolves_common_escapes() {
+        assert_eq!(
+            unescape(r#"a\nb\t\"c\" \' \\ \u{41}"#),
+            "a\nb\t\"c\" ' \\ \\u{41}"
+        );
+        assert_eq!(unescape("one \\\n    two"), "one two");
+        assert_eq!(unescape("trailing \\"), "trailing \\");
+    }
+
+    #[test]
+    fn strip_block_doc_removes_decoration_and_blank_edges() {
+        assert_eq!(strip_block_doc(" one "), "one");
+        assert_eq!(strip_block_doc("\n * a\n *\n *   b\n "), "a\n\n  b");
+        assert_eq!(strip_block_doc("\n    plain\n    lines\n"), "plain\nlines");
+    }
+}
@@ -1,6 +1,17 @@
 // src/extract.rs
-use crate::traverse::InfoExtractor;
+use crate::attributes::{
+    derive_names, has_attribute, inner_attributes, leading_attributes, AttributeInfo,
+};
+use crate::config::Config;
+use crate::crates::{CrateContext, CrateInfo};
+use crate::diagnostics::SyntaxDiagnostic;
+use crate::docs::{inner_doc_comment, outer_doc_comment, DocComment};
+use crate::modules::ModuleTree;
+use crate::traverse::{ExtractContext, InfoExtractor};
+use crate::visibility::Visibility;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use tree_sitter::Node;
 
 #[cfg(feature = "print_blocks")]
@@ -9,73 +20,379 @@ use crate::utils::print_blocks::PrintBlock;
 #[cfg(feature = "print_children_struct")]
 use crate::utils::print_children::{print_children_struct, print_struct_item};
 
+/// What every item kind records besides its own fields: where it is, what
+/// encloses it, and what is written on it. Saved flattened into the item.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ItemMeta {
+    /// Unique id of the item, see `ExtractContext::item_id`
+    pub id: String,
+    /// Path of the item including its crate, modules and enclosing items.
+    ///
+    /// Items without a name of their own (use declarations and their imports,
+    /// macro invocations, foreign blocks, and query items without a `@name`
+    /// capture) get the path of the scope they are in. An impl gets the path
+    /// of its self type, written `<crate::Type as Trait>` for trait impls,
+    /// and its methods `<crate::Type as Trait>::method`. A renamed
+    /// `extern crate` uses its alias.
+    pub qualified_path: String,
+    /// Id of the enclosing module, impl, trait or function, if any
+    pub parent_id: Option<String>,
+    pub file_path: String,
+    /// Byte span of the item. For an import, the span of its own tree, e.g.
+    /// `c::*` in `use a::{b, c::*}`.
+    pub start_position: usize,
+    pub end_position: usize,
+    pub crate_context: CrateContext,
+    /// The visibility as written. `Private` for items that take none, such as
+    /// impls and macro invocations. An import has the visibility of its
+    /// declaration, so `pub use` re-exports it.
+    pub visibility: Visibility,
+    /// `visibility` narrowed by the enclosing modules, see
+    /// `resolve_effective_visibility`
+    pub effective_visibility: Visibility,
+    pub doc_comment: Option<DocComment>,
+    pub attributes: Vec<AttributeInfo>,
+    /// Set when the item overlaps a syntax error, so its fields may be wrong
+    pub in_error_region: bool,
+}
+
+impl ItemMeta {
+    /// Where the item `node` is: its id, parent, file, span and crate.
+    pub fn at_node(node: Node, context: &ExtractContext) -> Self {
+        ItemMeta {
+            id: context.item_id(node),
+            parent_id: context.parent_id(),
+            file_path: context.file_path.clone(),
+            start_position: node.start_byte(),
+            end_position: node.end_byte(),
+            crate_context: context.crate_context.clone(),
+            ..Default::default()
+        }
+    }
+
+    /// `at_node` plus the visibility, docs and attributes written on `node`.
+    /// `qualified_path` is left empty until the item's name is known.
+    pub fn of_node(node: Node, code: &str, context: &ExtractContext) -> Self {
+        ItemMeta {
+            visibility: Visibility::of_node(node, code),
+            doc_comment: outer_doc_comment(node, code),
+            attributes: leading_attributes(node, code),
+            ..ItemMeta::at_node(node, context)
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ParameterInfo {
+    /// The bound name for plain identifier patterns, empty for patterns
+    /// such as `(a, b)`
     pub name: String,
+    /// The full pattern, e.g. `(a, b)` or `x`
+    pub pattern: String,
     pub type_name: String,
+    /// Declared `mut x: T`
+    pub is_mut: bool,
 }
 
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
-pub enum EnumVariantType {
-    // This is pretty much placeholder for now.
-    // It will likely get replaced when we add the `syn` crate.
-    Unit,
-    Tuple(Vec<String>),            // Store the types of the tuple fields
-    Struct(Vec<(String, String)>), // Store field names and types for struct-like variants
-    #[default]
-    Unspecified,
+/// How a method takes `self`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Receiver {
+    /// `self` or `mut self`
+    Value { is_mut: bool },
+    /// `&self` or `&'a self`
+    Ref { lifetime: Option<String> },
+    /// `&mut self` or `&'a mut self`
+    RefMut { lifetime: Option<String> },
+    /// An explicitly typed receiver such as `self: Box<Self>`
+    Typed { is_mut: bool, type_name: String },
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EnumVariantInfo {
     pub name: String,
-    pub variant_type: EnumVariantType,
+    /// `Named` for `Move { x: f32 }`, `Tuple` for `Move(f32)`, `Unit` for `Stop`
+    pub shape: StructShape,
+    pub fields: Vec<FieldInfo>,
+    /// The explicit discriminant expression, e.g. `1 << 3` in `Flag = 1 << 3`
+    pub discriminant: Option<String>,
+    /// Marked `#[default]` for `#[derive(Default)]`
+    pub is_default: bool,
+    pub doc_comment: Option<DocComment>,
+    pub attributes: Vec<AttributeInfo>,
+    pub start_position: usize,
+    pub end_position: usize,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct EnumInfo {
     pub name: String,
+    /// The generic parameter list, e.g. `<T: Asset>`
+    pub generics: Option<String>,
+    pub where_clause: Option<String>,
     pub variants: Vec<EnumVariantInfo>,
-    pub is_pub: bool,
-    pub start_position: usize,
-    pub end_position: usize,
-    pub file_path: String,
+    /// Every derived trait, including those derived through `cfg_attr`
+    pub derives: Vec<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ModInfo {
     pub name: String,
-    pub is_pub: bool,
-    pub start_position: usize,
-    pub end_position: usize,
-    pub file_path: String,
+    /// `//!` docs at the top of an inline module's body. The inner docs of a
+    /// module in its own file are in `ExtractedData::file_docs`.
+    pub inner_doc_comment: Option<DocComment>,
+    /// `#![...]` attributes at the top of an inline module's body. Those of a
+    /// module in its own file are in `ExtractedData::file_attributes`.
+    pub inner_attributes: Vec<AttributeInfo>,
+    /// `mod name { ... }` rather than `mod name;`
+    pub is_inline: bool,
+    /// The file given by a `#[path = "..."]` attribute, as written
+    pub path_attribute: Option<String>,
+    /// File of a `mod name;` declaration, when it was found. Filled in by
+    /// `resolve_module_tree`.
+    #[serde(default)]
+    pub resolved_file: Option<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MacroInfo {
     pub name: String,
-    pub start_position: usize,
-    pub end_position: usize,
-    pub file_path: String,
+    /// Id of the `macro_rules!` definition this invocation expands, when it
+    /// is part of the index. Filled in by `ExtractedData::link_macro_invocations`.
+    #[serde(default)]
+    pub definition_id: Option<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
 }
 
+/// One `(matcher) => { transcriber }` arm of a `macro_rules!` definition.
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MacroRuleInfo {
+    pub matcher: String,
+    pub transcriber: String,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MacroDefinitionInfo {
+    pub name: String,
+    /// Marked `#[macro_export]`, so usable from other crates
+    pub is_exported: bool,
+    pub rules: Vec<MacroRuleInfo>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FieldInfo {
+    /// The field name, or its index (`0`, `1`, ...) in a tuple struct
     pub name: String,
     pub type_name: String,
-    pub is_pub: bool,
+    pub visibility: Visibility,
+    pub doc_comment: Option<DocComment>,
+    pub attributes: Vec<AttributeInfo>,
+    pub start_position: usize,
+    pub end_position: usize,
+}
+
+/// How the fields of a struct are written.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StructShape {
+    /// `struct Player { name: String }`
+    Named,
+    /// `struct Velocity(Vec2);`
+    Tuple,
+    /// `struct Marker;`
+    #[default]
+    Unit,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct StructInfo {
     pub name: String,
-    pub is_pub: bool,
-    pub doc_comment: Option<String>, // Keeping doc_comment as Option<String> for now, can change to Vec<String> if needed for multiple doc comments
-    pub attributes: Vec<String>,
+    /// Every derived trait, including those derived through `cfg_attr`
+    pub derives: Vec<String>,
+    pub shape: StructShape,
+    /// The generic parameter list, e.g. `<T: Asset>`
+    pub generics: Option<String>,
+    pub where_clause: Option<String>,
+    pub fields: Vec<FieldInfo>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+/// A method declared in a trait, with or without a default body.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraitMethodInfo {
+    pub name: String,
+    /// Everything up to the body or the closing `;`, e.g. `fn area(&self) -> f32`
+    pub signature: String,
+    pub has_default_body: bool,
+    pub doc_comment: Option<DocComment>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssociatedTypeInfo {
+    pub name: String,
+    /// Bounds after the `:`, e.g. `["Clone", "Default"]`
+    pub bounds: Vec<String>,
+    pub doc_comment: Option<DocComment>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AssociatedConstInfo {
+    pub name: String,
+    pub type_name: String,
+    pub default_value: Option<String>,
+    pub doc_comment: Option<DocComment>,
+    pub attributes: Vec<AttributeInfo>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TraitInfo {
+    pub name: String,
+    /// The generic parameter list, e.g. `<T: Clone>`
+    pub generics: Option<String>,
+    pub supertraits: Vec<String>,
+    pub where_clause: Option<String>,
+    pub methods: Vec<TraitMethodInfo>,
+    pub associated_types: Vec<AssociatedTypeInfo>,
+    pub associated_consts: Vec<AssociatedConstInfo>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConstInfo {
+    pub name: String,
+    pub type_name: String,
+    /// Source text of the value expression. Only missing for trait consts
+    /// without a default.
+    pub value: Option<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StaticInfo {
+    pub name: String,
+    /// `static mut`
+    pub is_mut: bool,
+    pub type_name: String,
+    pub value: Option<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnionInfo {
+    pub name: String,
+    /// Every derived trait, including those derived through `cfg_attr`
+    pub derives: Vec<String>,
+    /// The generic parameter list, e.g. `<T: Copy>`
+    pub generics: Option<String>,
+    pub where_clause: Option<String>,
     pub fields: Vec<FieldInfo>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+/// An `extern crate name;` or `extern crate name as alias;` declaration.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternCrateInfo {
+    /// The crate as named in the declaration, e.g. `serde_json`
+    pub name: String,
+    /// The name the crate is bound to in this module when renamed with `as`
+    pub alias: Option<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+/// A function declared in a foreign block.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForeignFunctionInfo {
+    /// Id of the matching `FunctionInfo`, which holds the parameters
+    pub id: String,
+    pub name: String,
+    /// The declaration without the closing `;`, e.g. `fn abs(input: i32) -> i32`
+    pub signature: String,
+    pub visibility: Visibility,
+    pub doc_comment: Option<DocComment>,
+    pub attributes: Vec<AttributeInfo>,
     pub start_position: usize,
     pub end_position: usize,
-    pub file_path: String,
+}
+
+/// A static declared in a foreign block.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForeignStaticInfo {
+    /// Id of the matching `StaticInfo`
+    pub id: String,
+    pub name: String,
+    pub type_name: String,
+    /// `static mut`
+    pub is_mut: bool,
+    /// The declaration without the closing `;`, e.g. `static errno: i32`
+    pub signature: String,
+    pub visibility: Visibility,
+    pub doc_comment: Option<DocComment>,
+    pub attributes: Vec<AttributeInfo>,
+    pub start_position: usize,
+    pub end_position: usize,
+}
+
+/// An `extern "ABI" { ... }` block of foreign functions and statics.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ForeignModInfo {
+    /// ABI of the block; a bare `extern` is the `C` ABI
+    pub abi: String,
+    pub functions: Vec<ForeignFunctionInfo>,
+    pub statics: Vec<ForeignStaticInfo>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+/// An item matched by a query file rather than a hand-written extractor.
+///
+/// The well-known captures `@name`, `@visibility`, `@params` and `@body` fill
+/// the typed fields; every other capture is kept by name in `captures`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QueryItemInfo {
+    /// Stem of the query file the item was matched by, e.g. `function`
+    pub item_kind: String,
+    pub name: String,
+    pub params: Vec<String>,
+    pub body_start_position: Option<usize>,
+    pub body_end_position: Option<usize>,
+    pub captures: BTreeMap<String, Vec<String>>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
@@ -88,49 +405,538 @@ pub struct ExtractedData {
     pub mods: Vec<ModInfo>,
     pub enums: Vec<EnumInfo>,
     pub macros: Vec<MacroInfo>,
-    pub file_contents: std::collections::HashMap<String, String>,
+    #[serde(default)]
+    pub traits: Vec<TraitInfo>,
+    #[serde(default)]
+    pub consts: Vec<ConstInfo>,
+    #[serde(default)]
+    pub statics: Vec<StaticInfo>,
+    #[serde(default)]
+    pub macro_definitions: Vec<MacroDefinitionInfo>,
+    #[serde(default)]
+    pub unions: Vec<UnionInfo>,
+    #[serde(default)]
+    pub extern_crates: Vec<ExternCrateInfo>,
+    #[serde(default)]
+    pub foreign_mods: Vec<ForeignModInfo>,
+    /// The individual imports of `use_dependencies`
+    #[serde(default)]
+    pub imports: Vec<ImportInfo>,
+    /// Items found by the query extractors listed in `Config::queries`
+    #[serde(default)]
+    pub query_items: Vec<QueryItemInfo>,
+    /// Syntax errors found while parsing the indexed files
+    #[serde(default)]
+    pub diagnostics: Vec<SyntaxDiagnostic>,
+    // A `BTreeMap` keeps the saved output in a stable order between runs.
+    pub file_contents: BTreeMap<String, String>,
+    /// `//!` docs at the top of each file, keyed like `file_contents`
+    #[serde(default)]
+    pub file_docs: BTreeMap<String, DocComment>,
+    /// `#![...]` attributes at the top of each file, keyed like `file_contents`.
+    /// Files without any are left out.
+    #[serde(default)]
+    pub file_attributes: BTreeMap<String, Vec<AttributeInfo>>,
+    /// The modules of each indexed crate, see `resolve_module_tree`
+    #[serde(default)]
+    pub module_trees: Vec<ModuleTree>,
+    /// Indexed files that no crate root reaches through `mod` declarations
+    #[serde(default)]
+    pub orphan_files: Vec<String>,
+    /// Packages found in the manifests of the indexed roots
+    #[serde(default)]
+    pub crates: Vec<CrateInfo>,
+    /// The effective config of the run that produced this data
+    #[serde(default)]
+    pub config: Config,
+}
+
+/// What every extracted item has, so passes over all items can use
+/// `ExtractedData::items` instead of a loop per collection.
+pub trait ExtractedItem {
+    /// The extractor that produces the item. `None` for imports, which come
+    /// from use declarations, and for query items.
+    fn extractor_kind(&self) -> Option<ExtractorKind>;
+    /// Empty for items without a name, such as use declarations
+    fn name(&self) -> &str;
+    /// Another name the item is known by, such as the `as` name of an
+    /// `extern crate`.
+    fn alias(&self) -> Option<&str> {
+        None
+    }
+    /// Name of the item's kind in printed output, see `ExtractorKind::label`
+    fn kind_label(&self) -> &str {
+        self.extractor_kind().map_or("Import", |kind| kind.label())
+    }
+    fn meta(&self) -> &ItemMeta;
+    fn meta_mut(&mut self) -> &mut ItemMeta;
+    /// Whether the item can be written with a visibility, so its
+    /// `effective_visibility` is worth resolving.
+    fn has_visibility(&self) -> bool {
+        false
+    }
+}
+
+/// Implements `ExtractedItem` for an item struct with a `meta` field. Items
+/// listed with `visibility` can be written with one. A trailing block adds
+/// further methods.
+macro_rules! impl_extracted_item {
+    ($item:ty, $kind:expr, |$this:ident| $name:expr $(, { $($extra:tt)* })?) => {
+        impl_extracted_item!(@impl $item, $kind, |$this| $name, { $($($extra)*)? });
+    };
+    ($item:ty, $kind:expr, |$this:ident| $name:expr, visibility $(, { $($extra:tt)* })?) => {
+        impl_extracted_item!(@impl $item, $kind, |$this| $name, {
+            fn has_visibility(&self) -> bool {
+                true
+            }
+
+            $($($extra)*)?
+        });
+    };
+    (@impl $item:ty, $kind:expr, |$this:ident| $name:expr, { $($extra:tt)* }) => {
+        impl ExtractedItem for $item {
+            fn extractor_kind(&self) -> Option<ExtractorKind> {
+                $kind
+            }
+
+            fn name(&self) -> &str {
+                let $this = self;
+                $name
+            }
+
+            fn meta(&self) -> &ItemMeta {
+                &self.meta
+            }
+
+            fn meta_mut(&mut self) -> &mut ItemMeta {
+                &mut self.meta
+            }
+
+            $($extra)*
+        }
+    };
+}
+
+impl_extracted_item!(
+    StructInfo,
+    Some(ExtractorKind::Struct),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(
+    FunctionInfo,
+    Some(ExtractorKind::Function),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(
+    TypeAliasInfo,
+    Some(ExtractorKind::TypeAlias),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(ImplInfo, Some(ExtractorKind::Impl), |item| &item.name);
+impl_extracted_item!(
+    UseDependencyInfo,
+    Some(ExtractorKind::Use),
+    |_item| "",
+    visibility
+);
+impl_extracted_item!(
+    ModInfo,
+    Some(ExtractorKind::Mod),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(
+    EnumInfo,
+    Some(ExtractorKind::Enum),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(MacroInfo, Some(ExtractorKind::Macro), |item| &item.name);
+impl_extracted_item!(
+    TraitInfo,
+    Some(ExtractorKind::Trait),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(
+    ConstInfo,
+    Some(ExtractorKind::Const),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(
+    StaticInfo,
+    Some(ExtractorKind::Static),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(
+    MacroDefinitionInfo,
+    Some(ExtractorKind::MacroRules),
+    |item| &item.name
+);
+impl_extracted_item!(
+    UnionInfo,
+    Some(ExtractorKind::Union),
+    |item| &item.name,
+    visibility
+);
+impl_extracted_item!(
+    ExternCrateInfo,
+    Some(ExtractorKind::ExternCrate),
+    |item| &item.name,
+    visibility,
+    {
+        fn alias(&self) -> Option<&str> {
+            self.alias.as_deref()
+        }
+    }
+);
+impl_extracted_item!(ForeignModInfo, Some(ExtractorKind::ForeignMod), |_item| "");
+impl_extracted_item!(ImportInfo, None, |item| &item.path, visibility);
+impl_extracted_item!(QueryItemInfo, None, |item| &item.name, visibility, {
+    fn kind_label(&self) -> &str {
+        &self.item_kind
+    }
+});
+
+impl ExtractedData {
+    /// Every extracted item, collection by collection.
+    pub fn items(&self) -> impl Iterator<Item = &dyn ExtractedItem> {
+        fn erase<T: ExtractedItem>(items: &[T]) -> impl Iterator<Item = &dyn ExtractedItem> {
+            items.iter().map(|item| item as &dyn ExtractedItem)
+        }
+        erase(&self.structs)
+            .chain(erase(&self.functions))
+            .chain(erase(&self.type_aliases))
+            .chain(erase(&self.impls))
+            .chain(erase(&self.use_dependencies))
+            .chain(erase(&self.mods))
+            .chain(erase(&self.enums))
+            .chain(erase(&self.macros))
+            .chain(erase(&self.traits))
+            .chain(erase(&self.consts))
+            .chain(erase(&self.statics))
+            .chain(erase(&self.macro_definitions))
+            .chain(erase(&self.unions))
+            .chain(erase(&self.extern_crates))
+            .chain(erase(&self.foreign_mods))
+            .chain(erase(&self.imports))
+            .chain(erase(&self.query_items))
+    }
+
+    /// `items`, mutably.
+    pub fn items_mut(&mut self) -> impl Iterator<Item = &mut dyn ExtractedItem> {
+        fn erase<T: ExtractedItem>(
+            items: &mut [T],
+        ) -> impl Iterator<Item = &mut dyn ExtractedItem> {
+            items.iter_mut().map(|item| item as &mut dyn ExtractedItem)
+        }
+        erase(&mut self.structs)
+            .chain(erase(&mut self.functions))
+            .chain(erase(&mut self.type_aliases))
+            .chain(erase(&mut self.impls))
+            .chain(erase(&mut self.use_dependencies))
+            .chain(erase(&mut self.mods))
+            .chain(erase(&mut self.enums))
+            .chain(erase(&mut self.macros))
+            .chain(erase(&mut self.traits))
+            .chain(erase(&mut self.consts))
+            .chain(erase(&mut self.statics))
+            .chain(erase(&mut self.macro_definitions))
+            .chain(erase(&mut self.unions))
+            .chain(erase(&mut self.extern_crates))
+            .chain(erase(&mut self.foreign_mods))
+            .chain(erase(&mut self.imports))
+            .chain(erase(&mut self.query_items))
+    }
+
+    /// Appends everything extracted in `other` to `self`.
+    pub fn merge(&mut self, other: ExtractedData) {
+        self.structs.extend(other.structs);
+        self.functions.extend(other.functions);
+        self.type_aliases.extend(other.type_aliases);
+        self.impls.extend(other.impls);
+        self.use_dependencies.extend(other.use_dependencies);
+        self.mods.extend(other.mods);
+        self.enums.extend(other.enums);
+        self.macros.extend(other.macros);
+        self.traits.extend(other.traits);
+        self.consts.extend(other.consts);
+        self.statics.extend(other.statics);
+        self.macro_definitions.extend(other.macro_definitions);
+        self.unions.extend(other.unions);
+        self.extern_crates.extend(other.extern_crates);
+        self.foreign_mods.extend(other.foreign_mods);
+        self.imports.extend(other.imports);
+        self.query_items.extend(other.query_items);
+        self.diagnostics.extend(other.diagnostics);
+        self.file_contents.extend(other.file_contents);
+        self.file_docs.extend(other.file_docs);
+        self.file_attributes.extend(other.file_attributes);
+        for crate_info in other.crates {
+            if !self
+                .crates
+                .iter()
+                .any(|c| c.manifest_path == crate_info.manifest_path)
+            {
+                self.crates.push(crate_info);
+            }
+        }
+    }
+
+    /// Points every macro invocation at the `macro_rules!` definition it most
+    /// likely expands. Call once after all files are merged, since a macro is
+    /// often defined in a different file than it is used in.
+    ///
+    /// Without name resolution this is a heuristic. Candidates with the same
+    /// name are preferred in this order: defined earlier in the same file,
+    /// defined in the same crate, exported with `#[macro_export]`, anything else.
+    pub fn link_macro_invocations(&mut self) {
+        let mut definitions_by_name: HashMap<&str, Vec<&MacroDefinitionInfo>> = HashMap::new();
+        for definition in &self.macro_definitions {
+            definitions_by_name
+                .entry(definition.name.as_str())
+                .or_default()
+                .push(definition);
+        }
+
+        for invocation in &mut self.macros {
+            // `crate::make!` and `$crate::make!` name the same macro as `make!`.
+            let name = invocation.name.rsplit("::").next().unwrap_or_default();
+            invocation.definition_id = definitions_by_name.get(name).and_then(|candidates| {
+                candidates
+                    .iter()
+                    .min_by_key(|definition| {
+                        if definition.meta.file_path == invocation.meta.file_path
+                            && definition.meta.start_position < invocation.meta.start_position
+                        {
+                            0
+                        } else if !definition.meta.crate_context.crate_root.is_empty()
+                            && definition.meta.crate_context.crate_root
+                                == invocation.meta.crate_context.crate_root
+                        {
+                            1
+                        } else if definition.is_exported {
+                            2
+                        } else {
+                            3
+                        }
+                    })
+                    .map(|definition| definition.meta.id.clone())
+            });
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ImplInfo {
+    /// Same as `self_type`, kept so impls can be looked up like other items
     pub name: String,
-    pub is_pub: bool,
-    pub start_position: usize,
-    pub end_position: usize,
-    pub file_path: String,
+    /// The type the impl is for, e.g. `Foo<T>` in `impl<T> Display for Foo<T>`
+    pub self_type: String,
+    /// The implemented trait, e.g. `Display`, for trait impls
+    pub trait_name: Option<String>,
+    /// The generic parameter list, e.g. `<T: Clone>`
+    pub generics: Option<String>,
+    pub where_clause: Option<String>,
+    pub is_unsafe: bool,
+    /// A negative impl such as `impl !Send for Foo`
+    pub is_negative: bool,
+    /// Ids of the methods and associated functions in the body
+    pub function_ids: Vec<String>,
+    /// Ids of the associated types in the body
+    pub type_ids: Vec<String>,
+    /// Ids of the associated consts in the body
+    pub const_ids: Vec<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct UseDependencyInfo {
+    /// Ids of the `ImportInfo`s the declaration expands to, one per leaf
+    pub import_ids: Vec<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+/// What the path of an import is relative to.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathRoot {
+    /// `crate::...`, or `$crate::...` in a macro
+    Crate,
+    /// `self::...`
+    SelfModule,
+    /// `super::...`
+    Super,
+    /// `::...`, always an external crate
+    Global,
+    /// Starts with a name: an external crate or an item in scope
+    #[default]
+    Name,
+}
+
+/// One imported name of a `use` declaration. `use a::{b, c::*}` yields two
+/// imports, `a::b` and the glob `a::c::*`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImportInfo {
+    /// The full imported path, e.g. `bevy::math::Aabb2d`. Globs end at the
+    /// module they import from, and `a::{self}` imports `a`.
+    pub path: String,
     pub segments: Vec<String>,
+    /// The `as` name, e.g. `Bar` in `Foo as Bar`, or `_`
     pub alias: Option<String>,
-    pub is_pub: bool,
-    pub start_position: usize,
-    pub end_position: usize,
-    pub file_path: String,
+    pub is_glob: bool,
+    pub root: PathRoot,
+    /// Id of the `UseDependencyInfo` this import is part of
+    pub declaration_id: String,
+    /// Span of the whole `use` declaration
+    pub declaration_start_position: usize,
+    pub declaration_end_position: usize,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TypeAliasInfo {
     pub name: String,
     pub aliased_type: String,
-    pub is_pub: bool,
-    pub attributes: Vec<String>,
-    pub start_position: usize,
-    pub end_position: usize,
-    pub file_path: String,
+    /// The generic parameter list, e.g. `<T>`
+    pub generics: Option<String>,
+    pub where_clause: Option<String>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
 }
 
 #[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FunctionInfo {
     pub name: String,
+    /// Parameters after the receiver. The `...` of a variadic foreign
+    /// function comes last, with `...` as its type.
     pub parameters: Vec<ParameterInfo>,
+    /// Takes C variadic arguments, e.g. `fn printf(format: *const c_char, ...)`
+    pub is_variadic: bool,
     pub return_type: Option<String>,
-    pub is_pub: bool,
+    /// Whether the function takes `self` in any form, see `receiver`
     pub is_method: bool,
-    pub start_position: usize,
-    pub end_position: usize,
-    pub file_path: String,
+    pub receiver: Option<Receiver>,
+    /// The generic parameter list, e.g. `<'a, T: Clone>`
+    pub generics: Option<String>,
+    /// Lifetime parameters declared in `generics`, e.g. `'a`
+    pub lifetimes: Vec<String>,
+    pub where_clause: Option<String>,
+    pub is_async: bool,
+    pub is_const: bool,
+    /// Declared `unsafe`, or declared in an `extern` block, whose functions
+    /// are unsafe to call
+    pub is_unsafe: bool,
+    /// ABI of an `extern` function; a bare `extern` is the `C` ABI
+    pub abi: Option<String>,
+    /// Everything before the body, e.g. `pub fn new(x: u8) -> Self`
+    pub signature: String,
+    /// Span of the body block; `None` for trait methods without a default
+    pub body_start_position: Option<usize>,
+    pub body_end_position: Option<usize>,
+    #[serde(flatten)]
+    pub meta: ItemMeta,
+}
+
+/// Selects which extractors run during an indexing pass.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExtractorKind {
+    Struct,
+    Function,
+    TypeAlias,
+    Impl,
+    Use,
+    Mod,
+    Enum,
+    Macro,
+    Trait,
+    Const,
+    Static,
+    MacroRules,
+    Union,
+    ExternCrate,
+    ForeignMod,
+}
+
+impl ExtractorKind {
+    /// Name used in config files, and as the stem of the matching query file.
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExtractorKind::Struct => "struct",
+            ExtractorKind::Function => "function",
+            ExtractorKind::TypeAlias => "type_alias",
+            ExtractorKind::Impl => "impl",
+            ExtractorKind::Use => "use",
+            ExtractorKind::Mod => "mod",
+            ExtractorKind::Enum => "enum",
+            ExtractorKind::Macro => "macro",
+            ExtractorKind::Trait => "trait",
+            ExtractorKind::Const => "const",
+            ExtractorKind::Static => "static",
+            ExtractorKind::MacroRules => "macro_rules",
+            ExtractorKind::Union => "union",
+            ExtractorKind::ExternCrate => "extern_crate",
+            ExtractorKind::ForeignMod => "foreign_mod",
+        }
+    }
+
+    /// Name of the items of this kind in printed output, e.g. `Type Alias`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExtractorKind::Struct => "Struct",
+            ExtractorKind::Function => "Function",
+            ExtractorKind::TypeAlias => "Type Alias",
+            ExtractorKind::Impl => "Impl",
+            ExtractorKind::Use => "Use",
+            ExtractorKind::Mod => "Mod",
+            ExtractorKind::Enum => "Enum",
+            ExtractorKind::Macro => "Macro",
+            ExtractorKind::Trait => "Trait",
+            ExtractorKind::Const => "Const",
+            ExtractorKind::Static => "Static",
+            ExtractorKind::MacroRules => "Macro Definition",
+            ExtractorKind::Union => "Union",
+            ExtractorKind::ExternCrate => "Extern Crate",
+            ExtractorKind::ForeignMod => "Foreign Mod",
+        }
+    }
+
+    pub fn all() -> Vec<ExtractorKind> {
+        ExtractorKind::value_variants().to_vec()
+    }
+
+    pub fn extractor(&self) -> Box<dyn InfoExtractor> {
+        match self {
+            ExtractorKind::Struct => Box::new(StructInfoExtractor {}),
+            ExtractorKind::Function => Box::new(FunctionInfoExtractor {}),
+            ExtractorKind::TypeAlias => Box::new(TypeAliasInfoExtractor {}),
+            ExtractorKind::Impl => Box::new(ImplInfoExtractor {}),
+            ExtractorKind::Use => Box::new(UseDependencyInfoExtractor {}),
+            ExtractorKind::Mod => Box::new(ModInfoExtractor {}),
+            ExtractorKind::Enum => Box::new(EnumInfoExtractor {}),
+            ExtractorKind::Macro => Box::new(MacroInfoExtractor {}),
+            ExtractorKind::Trait => Box::new(TraitInfoExtractor {}),
+            ExtractorKind::Const => Box::new(ConstInfoExtractor {}),
+            ExtractorKind::Static => Box::new(StaticInfoExtractor {}),
+            ExtractorKind::MacroRules => Box::new(MacroDefinitionInfoExtractor {}),
+            ExtractorKind::Union => Box::new(UnionInfoExtractor {}),
+            ExtractorKind::ExternCrate => Box::new(ExternCrateInfoExtractor {}),
+            ExtractorKind::ForeignMod => Box::new(ForeignModInfoExtractor {}),
+        }
+    }
 }
 
 pub struct ImplInfoExtractor {}
@@ -140,28 +946,53 @@ impl InfoExtractor for ImplInfoExtractor {
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error> {
         if node.kind() == "impl_item" {
             let mut cursor = node.walk();
             let mut impl_info = ImplInfo {
-                start_position: node.start_byte(),
-                end_position: node.end_byte(),
-                file_path: file_path.to_string(),
+                self_type: field_text(node, "type", code).unwrap_or_default(),
+                trait_name: field_text(node, "trait", code),
+                generics: field_text(node, "type_parameters", code),
+                meta: ItemMeta::of_node(node, code, context),
                 ..Default::default()
             };
+            impl_info.name = impl_info.self_type.clone();
             for child in node.children(&mut cursor) {
                 match child.kind() {
-                    "visibility_modifier" => {
-                        impl_info.is_pub = true;
+                    "unsafe" => {
+                        impl_info.is_unsafe = true;
                     }
-                    "type_identifier" => {
-                        impl_info.name = child.utf8_text(code.as_bytes()).unwrap().to_string();
+                    "!" => {
+                        impl_info.is_negative = true;
+                    }
+                    "where_clause" => {
+                        impl_info.where_clause =
+                            Some(child.utf8_text(code.as_bytes())?.to_string());
+                    }
+                    "declaration_list" => {
+                        // Members are extracted by their own extractors; the impl
+                        // only refers to them by the ids those extractors assign.
+                        let mut member_cursor = child.walk();
+                        for member in child.named_children(&mut member_cursor) {
+                            let member_ids = match member.kind() {
+                                "function_item" => &mut impl_info.function_ids,
+                                "type_item" => &mut impl_info.type_ids,
+                                "const_item" => &mut impl_info.const_ids,
+                                _ => continue,
+                            };
+                            member_ids.push(context.item_id(member));
+                        }
                     }
                     _ => {}
                 }
             }
+            let self_path = context.qualified_path(&impl_info.self_type);
+            impl_info.meta.qualified_path = match &impl_info.trait_name {
+                Some(trait_name) => format!("<{} as {}>", self_path, trait_name),
+                None => self_path,
+            };
             extracted_data_.impls.push(impl_info);
         }
         Ok(())
@@ -181,22 +1012,9 @@ impl InfoExtractor for ImplInfoExtractor {
         // } [ #someday ]
     }
 
-    fn node_kind(&self) -> &'static str {
-        "impl_item"
-    }
-}
-
-fn extract_doc_comment(node: Node, code: &str) -> Option<String> {
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        if child.kind() == "outer_attribute" {
-            let text = child.utf8_text(code.as_bytes()).unwrap().to_string();
-            if text.starts_with("//!") {
-                return Some(text);
-            }
-        }
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["impl_item"]
     }
-    None
 }
 
 pub struct MacroInfoExtractor {}
@@ -206,15 +1024,14 @@ impl InfoExtractor for MacroInfoExtractor {
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error> {
         if node.kind() == "macro_invocation" {
             let mut macro_info = MacroInfo {
                 name: String::new(),
-                start_position: node.start_byte(),
-                end_position: node.end_byte(),
-                file_path: file_path.to_string(),
+                definition_id: None,
+                meta: ItemMeta::at_node(node, context),
             };
 
             // Extract macro name
@@ -224,13 +1041,63 @@ impl InfoExtractor for MacroInfoExtractor {
                 }
             }
 
+            macro_info.meta.qualified_path = context.qualified_path("");
             extracted_data_.macros.push(macro_info);
         }
         Ok(())
     }
 
-    fn node_kind(&self) -> &'static str {
-        "macro_invocation"
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["macro_invocation"]
+    }
+
+    // Macro arguments are plain token trees, so invocations never nest.
+    fn descend(&self) -> bool {
+        false
+    }
+}
+
+pub struct MacroDefinitionInfoExtractor {}
+
+impl InfoExtractor for MacroDefinitionInfoExtractor {
+    fn extract(
+        &self,
+        node: Node,
+        code: &str,
+        context: &ExtractContext,
+        extracted_data_: &mut ExtractedData,
+    ) -> Result<(), anyhow::Error> {
+        if node.kind() == "macro_definition" {
+            let meta = ItemMeta::of_node(node, code, context);
+            let mut definition_info = MacroDefinitionInfo {
+                name: field_text(node, "name", code).unwrap_or_default(),
+                is_exported: has_attribute(&meta.attributes, "macro_export"),
+                meta,
+                ..Default::default()
+            };
+
+            let mut cursor = node.walk();
+            for rule in node.children(&mut cursor) {
+                if rule.kind() == "macro_rule" {
+                    definition_info.rules.push(MacroRuleInfo {
+                        matcher: field_text(rule, "left", code).unwrap_or_default(),
+                        transcriber: field_text(rule, "right", code).unwrap_or_default(),
+                    });
+                }
+            }
+
+            definition_info.meta.qualified_path = context.qualified_path(&definition_info.name);
+            extracted_data_.macro_definitions.push(definition_info);
+        }
+        Ok(())
+    }
+
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["macro_definition"]
+    }
+
+    fn descend(&self) -> bool {
+        false
     }
 }
 
@@ -241,18 +1108,19 @@ impl InfoExtractor for EnumInfoExtractor {
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error> {
         if node.kind() == "enum_item" {
             let mut enum_info = EnumInfo {
                 name: String::new(),
+                generics: field_text(node, "type_parameters", code),
+                where_clause: None,
                 variants: Vec::new(),
-                is_pub: false,
-                start_position: node.start_byte(),
-                end_position: node.end_byte(),
-                file_path: file_path.to_string(),
+                derives: Vec::new(),
+                meta: ItemMeta::of_node(node, code, context),
             };
+            enum_info.derives = derive_names(&enum_info.meta.attributes);
 
             // Extract enum name
             if let Some(name_node) = node.child_by_field_name("name") {
@@ -261,6 +1129,14 @@ impl InfoExtractor for EnumInfoExtractor {
                 }
             }
 
+            let mut cursor = node.walk();
+            enum_info.where_clause = node
+                .children(&mut cursor)
+                .find(|child| child.kind() == "where_clause")
+                .map(|where_clause| where_clause.utf8_text(code.as_bytes()))
+                .transpose()?
+                .map(|where_clause| where_clause.to_string());
+
             // Extract enum variants
             if let Some(body_node) = node.child_by_field_name("body") {
                 let mut variant_cursor = body_node.walk();
@@ -271,75 +1147,41 @@ impl InfoExtractor for EnumInfoExtractor {
                 }
             }
 
+            enum_info.meta.qualified_path = context.qualified_path(&enum_info.name);
             extracted_data_.enums.push(enum_info);
         }
         Ok(())
     }
 
-    fn node_kind(&self) -> &'static str {
-        "enum_item"
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["enum_item"]
+    }
+
+    fn descend(&self) -> bool {
+        false
     }
 }
 
 fn extract_enum_variant(node: Node, code: &str, enum_info: &mut EnumInfo) {
+    let attributes = leading_attributes(node, code);
     let mut variant_info = EnumVariantInfo {
-        name: String::new(),
-        variant_type: EnumVariantType::Unit, // Default to Unit
+        name: field_text(node, "name", code).unwrap_or_default(),
+        discriminant: field_text(node, "value", code),
+        is_default: has_attribute(&attributes, "default"),
+        doc_comment: outer_doc_comment(node, code),
+        attributes,
+        start_position: node.start_byte(),
+        end_position: node.end_byte(),
+        ..Default::default()
     };
-    let mut name_cursor = node.walk();
-    for name_child in node.children(&mut name_cursor) {
-        match name_child.kind() {
-            "identifier" => {
-                if let Ok(name) = name_child.utf8_text(code.as_bytes()) {
-                    variant_info.name = name.to_string();
-                }
-            }
-            "ordered_field_declaration_list" => {
-                // Handle tuple-like variants
-                let mut tuple_fields: Vec<String> = Vec::new();
-                let mut field_cursor = name_child.walk();
-                for field in name_child.children(&mut field_cursor) {
-                    if field.kind() == "type" || field.kind() == "primitive_type" {
-                        if let Ok(field_type) = field.utf8_text(code.as_bytes()) {
-                            tuple_fields.push(field_type.to_string());
-                        }
-                    }
-                }
-                variant_info.variant_type = EnumVariantType::Tuple(tuple_fields);
-            }
-            "record_field_list" => {
-                // Handle struct-like variants
-                let mut struct_fields: Vec<(String, String)> = Vec::new();
-                let mut field_cursor = name_child.walk();
-                for field in name_child.children(&mut field_cursor) {
-                    if field.kind() == "field_declaration" {
-                        let mut field_name = String::new();
-                        let mut field_type = String::new();
-                        let mut field_cursor2 = field.walk();
-                        for field_child in field.children(&mut field_cursor2) {
-                            match field_child.kind() {
-                                "field_identifier" => {
-                                    if let Ok(name) = field_child.utf8_text(code.as_bytes()) {
-                                        field_name = name.to_string();
-                                    }
-                                }
-                                "type" | "primitive_type" => {
-                                    if let Ok(typ) = field_child.utf8_text(code.as_bytes()) {
-                                        field_type = typ.to_string();
-                                    }
-                                }
-                                _ => {}
-                            }
-                        }
-                        struct_fields.push((field_name, field_type));
-                    }
-                }
-                variant_info.variant_type = EnumVariantType::Struct(struct_fields);
-            }
-            _ => {}
-        }
+    if let Some(body) = node.child_by_field_name("body") {
+        variant_info.shape = match body.kind() {
+            "ordered_field_declaration_list" => StructShape::Tuple,
+            _ => StructShape::Named,
+        };
+        variant_info.fields = extract_fields(body, code);
     }
-    enum_info.variants.push(variant_info.clone());
+    enum_info.variants.push(variant_info);
 }
 
 pub struct ModInfoExtractor {}
@@ -349,40 +1191,49 @@ impl InfoExtractor for ModInfoExtractor {
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error> {
         if node.kind() == "mod_item" {
             let mut mod_info = ModInfo {
                 name: String::new(),
-                is_pub: false,
-                start_position: node.start_byte(),
-                end_position: node.end_byte(),
-                file_path: file_path.to_string(),
+                inner_doc_comment: node
+                    .child_by_field_name("body")
+                    .and_then(|body| inner_doc_comment(body, code)),
+                inner_attributes: node
+                    .child_by_field_name("body")
+                    .map(|body| inner_attributes(body, code))
+                    .unwrap_or_default(),
+                is_inline: node.child_by_field_name("body").is_some(),
+                path_attribute: None,
+                resolved_file: None,
+                meta: ItemMeta::of_node(node, code, context),
             };
 
             let mut cursor = node.walk();
             for child in node.children(&mut cursor) {
-                match child.kind() {
-                    "visibility_modifier" => {
-                        mod_info.is_pub = true;
-                    }
-                    "identifier" => {
-                        if let Ok(name) = child.utf8_text(code.as_bytes()) {
-                            mod_info.name = name.to_string();
-                        }
+                if child.kind() == "identifier" {
+                    if let Ok(name) = child.utf8_text(code.as_bytes()) {
+                        mod_info.name = name.to_string();
                     }
-                    _ => {}
                 }
             }
 
+            mod_info.path_attribute = mod_info
+                .meta
+                .attributes
+                .iter()
+                .find(|attribute| attribute.path == "path")
+                .and_then(|attribute| attribute.value.as_deref())
+                .map(|path| path.trim_matches('"').to_string());
+            mod_info.meta.qualified_path = context.qualified_path(&mod_info.name);
             extracted_data_.mods.push(mod_info);
         }
         Ok(())
     }
 
-    fn node_kind(&self) -> &'static str {
-        "mod_item"
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["mod_item"]
     }
 }
 
@@ -394,78 +1245,132 @@ impl InfoExtractor for UseDependencyInfoExtractor {
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error> {
         if node.kind() == "use_declaration" {
             let mut use_dependency_info = UseDependencyInfo {
-                start_position: node.start_byte(),
-                end_position: node.end_byte(),
-                file_path: file_path.to_string(),
+                meta: ItemMeta::of_node(node, code, context),
                 ..Default::default()
             };
 
-            extract_use_segments(node, code, &mut use_dependency_info);
-
+            use_dependency_info.meta.qualified_path = context.qualified_path("");
+            if let Some(argument) = node.child_by_field_name("argument") {
+                let mut imports = Vec::new();
+                expand_use_tree(argument, code, &[], &mut imports);
+                for (segments, alias, is_glob, tree) in imports {
+                    let import_info = ImportInfo {
+                        path: segments.join("::"),
+                        root: path_root(&segments),
+                        segments: segments
+                            .into_iter()
+                            .filter(|segment| !segment.is_empty())
+                            .collect(),
+                        alias,
+                        is_glob,
+                        declaration_id: use_dependency_info.meta.id.clone(),
+                        declaration_start_position: node.start_byte(),
+                        declaration_end_position: node.end_byte(),
+                        meta: ItemMeta {
+                            visibility: use_dependency_info.meta.visibility.clone(),
+                            qualified_path: use_dependency_info.meta.qualified_path.clone(),
+                            ..ItemMeta::at_node(tree, context)
+                        },
+                    };
+                    use_dependency_info
+                        .import_ids
+                        .push(import_info.meta.id.clone());
+                    extracted_data_.imports.push(import_info);
+                }
+            }
             extracted_data_.use_dependencies.push(use_dependency_info);
         }
         Ok(())
     }
 
-    fn node_kind(&self) -> &'static str {
-        "use_declaration"
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["use_declaration"]
     }
-}
 
-fn extract_use_segments(node: Node, code: &str, use_dependency_info: &mut UseDependencyInfo) {
-    let mut cursor = node.walk();
-    for child in node.children(&mut cursor) {
-        match child.kind() {
-            "scoped_identifier" | "path" | "use_group" | "identifier" => {
-                extract_path_segments(child, code, &mut use_dependency_info.segments);
-            }
-            "use_as_clause" => {
-                // Handle the "as" alias
-                let mut alias_cursor = child.walk();
-                for alias_child in child.children(&mut alias_cursor) {
-                    if alias_child.kind() == "identifier"
-                        || alias_child.kind() == "scoped_identifier"
-                    {
-                        if let Ok(alias) = alias_child.utf8_text(code.as_bytes()) {
-                            use_dependency_info.alias = Some(alias.to_string());
-                            break;
-                        }
-                    }
-                }
-            }
-            "use_wildcard" => {
-                use_dependency_info.segments.push("*".to_string());
-            }
-            _ => {}
-        }
+    fn descend(&self) -> bool {
+        false
     }
 }
 
-fn extract_path_segments(node: Node, code: &str, segments: &mut Vec<String>) {
-    match node.kind() {
-        "identifier" => {
-            if let Ok(segment) = node.utf8_text(code.as_bytes()) {
-                segments.push(segment.to_string());
+/// An expanded import: its path segments, alias, whether it is a glob, and
+/// the use tree node it comes from.
+type UseLeaf<'tree> = (Vec<String>, Option<String>, bool, Node<'tree>);
+
+/// Expands a use tree into its leaves, each with the full path from the root
+/// of the declaration. A leading `::` is kept as an empty first segment.
+fn expand_use_tree<'tree>(
+    tree: Node<'tree>,
+    code: &str,
+    prefix: &[String],
+    leaves: &mut Vec<UseLeaf<'tree>>,
+) {
+    match tree.kind() {
+        "use_as_clause" => {
+            if let Some(path) = tree.child_by_field_name("path") {
+                let segments = join_use_path(prefix, path, code);
+                leaves.push((segments, field_text(tree, "alias", code), false, tree));
             }
         }
-        "scoped_identifier" | "path" => {
-            let mut cursor = node.walk();
-            for child in node.children(&mut cursor) {
-                extract_path_segments(child, code, segments);
+        "use_wildcard" => {
+            let mut segments = prefix.to_vec();
+            let mut cursor = tree.walk();
+            if let Some(path) = tree.named_children(&mut cursor).next() {
+                segments = join_use_path(prefix, path, code);
+            } else if tree.child(0).is_some_and(|first| first.kind() == "::") {
+                segments.push(String::new());
             }
+            leaves.push((segments, None, true, tree));
         }
-        "use_group" => {
-            let mut cursor = node.walk();
-            for child in node.children(&mut cursor) {
-                extract_path_segments(child, code, segments);
+        "scoped_use_list" => {
+            let mut segments = prefix.to_vec();
+            if let Some(path) = tree.child_by_field_name("path") {
+                segments = join_use_path(prefix, path, code);
+            } else if tree.child(0).is_some_and(|first| first.kind() == "::") {
+                segments.push(String::new());
+            }
+            if let Some(list) = tree.child_by_field_name("list") {
+                expand_use_tree(list, code, &segments, leaves);
             }
         }
-        _ => {}
+        "use_list" => {
+            let mut cursor = tree.walk();
+            for item in tree.named_children(&mut cursor) {
+                expand_use_tree(item, code, prefix, leaves);
+            }
+        }
+        "line_comment" | "block_comment" => {}
+        _ => leaves.push((join_use_path(prefix, tree, code), None, false, tree)),
+    }
+}
+
+/// `prefix` followed by the segments of the path `node`. A `self` inside a
+/// group names the group's own path, as in `use std::io::{self, Read}`.
+fn join_use_path(prefix: &[String], node: Node, code: &str) -> Vec<String> {
+    let text: String = node
+        .utf8_text(code.as_bytes())
+        .unwrap_or_default()
+        .split_whitespace()
+        .collect();
+    let mut segments = prefix.to_vec();
+    if text == "self" && !prefix.is_empty() {
+        return segments;
+    }
+    segments.extend(text.split("::").map(|segment| segment.to_string()));
+    segments
+}
+
+fn path_root(segments: &[String]) -> PathRoot {
+    match segments.first().map(|segment| segment.as_str()) {
+        Some("crate") | Some("$crate") => PathRoot::Crate,
+        Some("self") => PathRoot::SelfModule,
+        Some("super") => PathRoot::Super,
+        Some("") => PathRoot::Global,
+        _ => PathRoot::Name,
     }
 }
 
@@ -476,51 +1381,38 @@ impl InfoExtractor for TypeAliasInfoExtractor {
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error> {
         if node.kind() == "type_item" {
             let mut type_alias_info = TypeAliasInfo {
-                name: String::new(),
-                aliased_type: String::new(),
-                is_pub: false,
-                attributes: Vec::new(),
-                start_position: node.start_byte(),
-                end_position: node.end_byte(),
-                file_path: file_path.to_string(),
+                name: field_text(node, "name", code).unwrap_or_default(),
+                aliased_type: field_text(node, "type", code).unwrap_or_default(),
+                generics: field_text(node, "type_parameters", code),
+                meta: ItemMeta::of_node(node, code, context),
+                ..Default::default()
             };
 
             let mut cursor = node.walk();
-
             for child in node.children(&mut cursor) {
-                match child.kind() {
-                    "visibility_modifier" => {
-                        type_alias_info.is_pub = true;
-                    }
-                    "type_identifier" => {
-                        type_alias_info.name =
-                            child.utf8_text(code.as_bytes()).unwrap().to_string();
-                    }
-                    "type" => {
-                        type_alias_info.aliased_type =
-                            child.utf8_text(code.as_bytes()).unwrap().to_string();
-                    }
-                    "attribute" => {
-                        type_alias_info
-                            .attributes
-                            .push(child.utf8_text(code.as_bytes()).unwrap().to_string());
-                    }
-                    _ => {}
+                if child.kind() == "where_clause" {
+                    type_alias_info.where_clause =
+                        Some(child.utf8_text(code.as_bytes())?.to_string());
                 }
             }
 
+            type_alias_info.meta.qualified_path = context.qualified_path(&type_alias_info.name);
             extracted_data_.type_aliases.push(type_alias_info);
         }
         Ok(())
     }
 
-    fn node_kind(&self) -> &'static str {
-        "type_item"
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["type_item"]
+    }
+
+    fn descend(&self) -> bool {
+        false
     }
 }
 
@@ -531,44 +1423,20 @@ impl InfoExtractor for StructInfoExtractor {
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error> {
         if node.kind() == "struct_item" {
             #[cfg(feature = "print_children_struct")]
             print_struct_item(node);
 
+            let meta = ItemMeta::of_node(node, code, context);
             let mut struct_info = StructInfo {
-                start_position: node.start_byte(),
-                end_position: node.end_byte(), // Initial end_position
-                file_path: file_path.to_string(),
+                derives: derive_names(&meta.attributes),
+                meta,
                 ..Default::default()
             };
 
-            // Extract leading doc comments by iterating backwards through preceding siblings
-            let mut current_node = node;
-            while let Some(previous_sibling) = current_node.prev_sibling() {
-                if previous_sibling.kind() == "line_comment" {
-                    let comment_text = previous_sibling.utf8_text(code.as_bytes()).unwrap().trim();
-                    if comment_text.starts_with("///") {
-                        // Prepend the comment to the existing doc_comment (if any)
-                        struct_info.doc_comment = match struct_info.doc_comment {
-                            Some(existing_comment) => {
-                                Some(format!("{}\n{}", comment_text, existing_comment))
-                            }
-                            None => Some(comment_text.to_string()),
-                        };
-                    } else {
-                        // If it's not a doc comment, stop searching
-                        break;
-                    }
-                    current_node = previous_sibling; // Move to the previous sibling
-                } else {
-                    // If it's not a comment, stop searching
-                    break;
-                }
-            }
-
             let mut cursor = node.walk();
             let mut max_end_byte = node.end_byte(); // Initialize with the node's initial end byte
 
@@ -579,43 +1447,23 @@ impl InfoExtractor for StructInfoExtractor {
                 max_end_byte = std::cmp::max(max_end_byte, child.end_byte()); // Update max_end_byte
 
                 match child.kind() {
-                    "attribute_item" => {
-                        if let Ok(attribute) = child.utf8_text(code.as_bytes()) {
-                            struct_info.attributes.push(attribute.to_string());
-                        }
+                    "type_identifier" => {
+                        struct_info.name = child.utf8_text(code.as_bytes())?.to_string();
                     }
-                    "visibility_modifier" => {
-                        struct_info.is_pub = true;
+                    "type_parameters" => {
+                        struct_info.generics = Some(child.utf8_text(code.as_bytes())?.to_string());
                     }
-                    "type_identifier" => {
-                        struct_info.name = child.utf8_text(code.as_bytes()).unwrap().to_string();
+                    "where_clause" => {
+                        struct_info.where_clause =
+                            Some(child.utf8_text(code.as_bytes())?.to_string());
                     }
                     "field_declaration_list" => {
-                        let mut field_cursor = child.walk();
-                        for field in child.children(&mut field_cursor) {
-                            if field.kind() == "field_declaration" {
-                                let mut field_info = FieldInfo::default();
-                                let mut field_cursor2 = field.walk();
-                                for field_child in field.children(&mut field_cursor2) {
-                                    match field_child.kind() {
-                                        "field_identifier" => {
-                                            if let Ok(name) = field_child.utf8_text(code.as_bytes())
-                                            {
-                                                field_info.name = name.to_string();
-                                            }
-                                        }
-                                        "type" => {
-                                            if let Ok(typ) = field_child.utf8_text(code.as_bytes())
-                                            {
-                                                field_info.type_name = typ.to_string();
-                                            }
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                                struct_info.fields.push(field_info);
-                            }
-                        }
+                        struct_info.shape = StructShape::Named;
+                        struct_info.fields = extract_fields(child, code);
+                    }
+                    "ordered_field_declaration_list" => {
+                        struct_info.shape = StructShape::Tuple;
+                        struct_info.fields = extract_fields(child, code);
                     }
                     "block" => {
                         // handle block - not relevant for struct definition itself
@@ -624,18 +1472,79 @@ impl InfoExtractor for StructInfoExtractor {
                 }
             }
             // Update end_position after processing all children
-            // struct_info.end_position = node.end_byte(); // revert to this if the below does not work
-            struct_info.end_position = max_end_byte;
+            // struct_info.meta.end_position = node.end_byte(); // revert to this if the below does not work
+            struct_info.meta.end_position = max_end_byte;
+            struct_info.meta.qualified_path = context.qualified_path(&struct_info.name);
             extracted_data_.structs.push(struct_info);
         }
         Ok(())
     }
 
-    fn node_kind(&self) -> &'static str {
-        "struct_item"
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["struct_item"]
+    }
+
+    fn descend(&self) -> bool {
+        false
     }
 }
 
+/// The fields of a `field_declaration_list` (`{ a: u8 }`) or an
+/// `ordered_field_declaration_list` (`(pub u8, String)`).
+fn extract_fields(body: Node, code: &str) -> Vec<FieldInfo> {
+    let mut fields = Vec::new();
+    let mut cursor = body.walk();
+    if body.kind() == "field_declaration_list" {
+        for field in body.named_children(&mut cursor) {
+            if field.kind() != "field_declaration" {
+                continue;
+            }
+            fields.push(FieldInfo {
+                name: field_text(field, "name", code).unwrap_or_default(),
+                type_name: field_text(field, "type", code).unwrap_or_default(),
+                visibility: Visibility::of_node(field, code),
+                doc_comment: outer_doc_comment(field, code),
+                attributes: leading_attributes(field, code),
+                start_position: field.start_byte(),
+                end_position: field.end_byte(),
+            });
+        }
+        return fields;
+    }
+
+    // Tuple fields are not wrapped in a node of their own, so each one starts
+    // at its visibility modifier, or at its type when it has none.
+    let mut field_start: Option<Node> = None;
+    for child in body.named_children(&mut cursor) {
+        match child.kind() {
+            "attribute_item" | "line_comment" | "block_comment" => {}
+            "visibility_modifier" => field_start = Some(child),
+            _ => {
+                let start = field_start.take().unwrap_or(child);
+                let visibility = match start.kind() {
+                    "visibility_modifier" => {
+                        Visibility::parse(start.utf8_text(code.as_bytes()).unwrap_or_default())
+                    }
+                    _ => Visibility::Private,
+                };
+                fields.push(FieldInfo {
+                    name: fields.len().to_string(),
+                    type_name: child
+                        .utf8_text(code.as_bytes())
+                        .map(|type_name| type_name.to_string())
+                        .unwrap_or_default(),
+                    visibility,
+                    doc_comment: outer_doc_comment(start, code),
+                    attributes: leading_attributes(start, code),
+                    start_position: start.start_byte(),
+                    end_position: child.end_byte(),
+                });
+            }
+        }
+    }
+    fields
+}
+
 pub struct FunctionInfoExtractor {}
 
 impl InfoExtractor for FunctionInfoExtractor {
@@ -643,89 +1552,943 @@ impl InfoExtractor for FunctionInfoExtractor {
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error> {
-        if node.kind() == "function_item" {
+        if matches!(node.kind(), "function_item" | "function_signature_item") {
+            let body = node.child_by_field_name("body");
+            let signature_end = body.map_or(node.end_byte(), |body| body.start_byte());
             let mut function_info = FunctionInfo {
-                start_position: node.start_byte(),
-                end_position: node.end_byte(),
-                file_path: file_path.to_string(),
+                name: field_text(node, "name", code).unwrap_or_default(),
+                return_type: field_text(node, "return_type", code),
+                generics: field_text(node, "type_parameters", code),
+                signature: code[node.start_byte()..signature_end]
+                    .trim_end()
+                    .trim_end_matches(';')
+                    .to_string(),
+                body_start_position: body.map(|body| body.start_byte()),
+                body_end_position: body.map(|body| body.end_byte()),
+                meta: ItemMeta::of_node(node, code, context),
                 ..Default::default()
             };
             let mut cursor = node.walk();
             for child in node.children(&mut cursor) {
                 match child.kind() {
-                    "visibility_modifier" => {
-                        function_info.is_pub = true;
+                    "function_modifiers" => {
+                        extract_function_modifiers(child, code, &mut function_info);
                     }
-                    "identifier" => {
-                        function_info.name = child.utf8_text(code.as_bytes()).unwrap().to_string();
+                    "type_parameters" => {
+                        let mut param_cursor = child.walk();
+                        function_info.lifetimes = child
+                            .named_children(&mut param_cursor)
+                            .filter(|param| param.kind() == "lifetime_parameter")
+                            .filter_map(|param| param.utf8_text(code.as_bytes()).ok())
+                            .map(|lifetime| lifetime.to_string())
+                            .collect();
                     }
-                    "parameters" => {
-                        if let Some(params_node) = node.child_by_field_name("parameters") {
-                            let mut param_cursor = params_node.walk();
-                            for param in params_node.children(&mut param_cursor) {
-                                if param.kind() == "self_parameter" {
-                                    if let Ok(self_param) = param.utf8_text(code.as_bytes()) {
-                                        if self_param == "&self"
-                                            || self_param == "&mut self"
-                                            || self_param == "self"
-                                        {
-                                            function_info.is_method = true;
-                                        }
-                                    }
-                                }
-                                if param.kind() == "parameter" {
-                                    let mut param_info = ParameterInfo::default();
-                                    let mut param_cursor2 = param.walk();
-                                    for param_child in param.children(&mut param_cursor2) {
-                                        match param_child.kind() {
-                                            "identifier" => {
-                                                if let Ok(name) =
-                                                    param_child.utf8_text(code.as_bytes())
-                                                {
-                                                    param_info.name = name.to_string();
-                                                }
-                                            }
-                                            "type" => {
-                                                if let Ok(type_name) =
-                                                    param_child.utf8_text(code.as_bytes())
-                                                {
-                                                    param_info.type_name = type_name.to_string();
-                                                }
-                                            }
-                                            "generic_type" => {
-                                                if let Ok(type_name) =
-                                                    param_child.utf8_text(code.as_bytes())
-                                                {
-                                                    param_info.type_name = type_name.to_string();
-                                                }
-                                            }
-                                            _ => {}
-                                        }
-                                    }
-                                    function_info.parameters.push(param_info);
-                                }
-                            }
-                        }
+                    "where_clause" => {
+                        function_info.where_clause =
+                            Some(child.utf8_text(code.as_bytes())?.to_string());
                     }
-                    "return_type" => {
-                        if let Some(return_type_node) = node.child_by_field_name("return_type") {
-                            if let Ok(return_type) = return_type_node.utf8_text(code.as_bytes()) {
-                                function_info.return_type = Some(return_type.to_string());
-                            }
-                        }
+                    "parameters" => {
+                        extract_parameters(child, code, &mut function_info)?;
                     }
                     _ => {}
                 }
             }
+            // Functions in a foreign block take the ABI of the block and are
+            // unsafe to call.
+            if let Some(foreign_mod) = enclosing_foreign_mod(node) {
+                function_info.abi = Some(foreign_mod_abi(foreign_mod, code));
+                function_info.is_unsafe = true;
+            }
+            function_info.is_method = function_info.receiver.is_some();
+            function_info.meta.qualified_path = context.qualified_path(&function_info.name);
             extracted_data_.functions.push(function_info);
         }
         Ok(())
     }
 
-    fn node_kind(&self) -> &'static str {
-        "function_item"
+    // Trait methods without a default body are `function_signature_item`s.
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["function_item", "function_signature_item"]
+    }
+}
+
+/// Reads `const`, `async`, `unsafe` and `extern "ABI"` from a `function_modifiers` node.
+fn extract_function_modifiers(modifiers: Node, code: &str, function_info: &mut FunctionInfo) {
+    let mut cursor = modifiers.walk();
+    for modifier in modifiers.children(&mut cursor) {
+        match modifier.kind() {
+            "async" => function_info.is_async = true,
+            "const" => function_info.is_const = true,
+            "unsafe" => function_info.is_unsafe = true,
+            "extern_modifier" => function_info.abi = Some(extern_abi(modifier, code)),
+            _ => {}
+        }
+    }
+}
+
+/// The ABI named by an `extern_modifier`, which is `C` when none is given.
+fn extern_abi(modifier: Node, code: &str) -> String {
+    let mut cursor = modifier.walk();
+    let abi = modifier
+        .named_children(&mut cursor)
+        .find(|child| child.kind() == "string_literal")
+        .and_then(|literal| literal.utf8_text(code.as_bytes()).ok())
+        .map(|literal| literal.trim_matches('"').to_string());
+    abi.unwrap_or_else(|| "C".to_string())
+}
+
+/// The ABI of a `foreign_mod_item`, e.g. `C` for both `extern {}` and
+/// `extern "C" {}`.
+fn foreign_mod_abi(foreign_mod: Node, code: &str) -> String {
+    let mut cursor = foreign_mod.walk();
+    let modifier = foreign_mod
+        .children(&mut cursor)
+        .find(|child| child.kind() == "extern_modifier");
+    match modifier {
+        Some(modifier) => extern_abi(modifier, code),
+        None => "C".to_string(),
+    }
+}
+
+/// The `foreign_mod_item` a function or static is declared in, if any.
+fn enclosing_foreign_mod(node: Node) -> Option<Node> {
+    let foreign_mod = node.parent()?.parent()?;
+    (foreign_mod.kind() == "foreign_mod_item").then_some(foreign_mod)
+}
+
+/// The text of an item up to its closing `;`.
+fn declaration_text(node: Node, code: &str) -> String {
+    code[node.start_byte()..node.end_byte()]
+        .trim_end()
+        .trim_end_matches(';')
+        .trim_end()
+        .to_string()
+}
+
+fn extract_parameters(
+    parameters: Node,
+    code: &str,
+    function_info: &mut FunctionInfo,
+) -> Result<(), anyhow::Error> {
+    let mut cursor = parameters.walk();
+    for param in parameters.named_children(&mut cursor) {
+        let mut param_cursor = param.walk();
+        let is_mut = param
+            .children(&mut param_cursor)
+            .any(|child| child.kind() == "mutable_specifier");
+        match param.kind() {
+            // `self`, `mut self`, `&self`, `&'a mut self`
+            "self_parameter" => {
+                let mut self_cursor = param.walk();
+                let children: Vec<Node> = param.children(&mut self_cursor).collect();
+                let lifetime = children
+                    .iter()
+                    .find(|child| child.kind() == "lifetime")
+                    .map(|lifetime| lifetime.utf8_text(code.as_bytes()))
+                    .transpose()?
+                    .map(|lifetime| lifetime.to_string());
+                let is_reference = children.iter().any(|child| child.kind() == "&");
+                function_info.receiver = Some(match (is_reference, is_mut) {
+                    (true, true) => Receiver::RefMut { lifetime },
+                    (true, false) => Receiver::Ref { lifetime },
+                    (false, is_mut) => Receiver::Value { is_mut },
+                });
+            }
+            "parameter" => {
+                let pattern = param.child_by_field_name("pattern");
+                let type_name = field_text(param, "type", code).unwrap_or_default();
+                // `self: Box<Self>` is a typed receiver, not a regular parameter.
+                if pattern.is_some_and(|pattern| pattern.kind() == "self") {
+                    function_info.receiver = Some(Receiver::Typed { is_mut, type_name });
+                    continue;
+                }
+                let pattern_text = field_text(param, "pattern", code).unwrap_or_default();
+                let name = match pattern.map(|pattern| pattern.kind()) {
+                    Some("identifier") => pattern_text.clone(),
+                    _ => String::new(),
+                };
+                function_info.parameters.push(ParameterInfo {
+                    name,
+                    pattern: pattern_text,
+                    type_name,
+                    is_mut,
+                });
+            }
+            // `...` or `args: ...`, only allowed last in foreign functions
+            "variadic_parameter" => {
+                let pattern = param.child_by_field_name("pattern");
+                let pattern_text = field_text(param, "pattern", code).unwrap_or_default();
+                let name = match pattern.map(|pattern| pattern.kind()) {
+                    Some("identifier") => pattern_text.clone(),
+                    _ => String::new(),
+                };
+                function_info.parameters.push(ParameterInfo {
+                    name,
+                    pattern: pattern_text,
+                    type_name: "...".to_string(),
+                    is_mut,
+                });
+                function_info.is_variadic = true;
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+pub struct TraitInfoExtractor {}
+
+impl InfoExtractor for TraitInfoExtractor {
+    fn extract(
+        &self,
+        node: Node,
+        code: &str,
+        context: &ExtractContext,
+        extracted_data_: &mut ExtractedData,
+    ) -> Result<(), anyhow::Error> {
+        if node.kind() == "trait_item" {
+            let mut trait_info = TraitInfo {
+                generics: field_text(node, "type_parameters", code),
+                meta: ItemMeta::of_node(node, code, context),
+                ..Default::default()
+            };
+
+            let mut cursor = node.walk();
+            for child in node.children(&mut cursor) {
+                match child.kind() {
+                    "type_identifier" => {
+                        trait_info.name = child.utf8_text(code.as_bytes())?.to_string();
+                    }
+                    "trait_bounds" => {
+                        trait_info.supertraits = bound_list(child, code);
+                    }
+                    "where_clause" => {
+                        trait_info.where_clause =
+                            Some(child.utf8_text(code.as_bytes())?.to_string());
+                    }
+                    "declaration_list" => {
+                        extract_trait_items(child, code, &mut trait_info)?;
+                    }
+                    _ => {}
+                }
+            }
+
+            trait_info.meta.qualified_path = context.qualified_path(&trait_info.name);
+            extracted_data_.traits.push(trait_info);
+        }
+        Ok(())
+    }
+
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["trait_item"]
+    }
+}
+
+pub struct ConstInfoExtractor {}
+
+impl InfoExtractor for ConstInfoExtractor {
+    fn extract(
+        &self,
+        node: Node,
+        code: &str,
+        context: &ExtractContext,
+        extracted_data_: &mut ExtractedData,
+    ) -> Result<(), anyhow::Error> {
+        if node.kind() == "const_item" {
+            let mut const_info = ConstInfo {
+                name: field_text(node, "name", code).unwrap_or_default(),
+                type_name: field_text(node, "type", code).unwrap_or_default(),
+                value: field_text(node, "value", code),
+                meta: ItemMeta::of_node(node, code, context),
+            };
+            const_info.meta.qualified_path = context.qualified_path(&const_info.name);
+            extracted_data_.consts.push(const_info);
+        }
+        Ok(())
+    }
+
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["const_item"]
+    }
+
+    fn descend(&self) -> bool {
+        false
+    }
+}
+
+pub struct StaticInfoExtractor {}
+
+impl InfoExtractor for StaticInfoExtractor {
+    fn extract(
+        &self,
+        node: Node,
+        code: &str,
+        context: &ExtractContext,
+        extracted_data_: &mut ExtractedData,
+    ) -> Result<(), anyhow::Error> {
+        if node.kind() == "static_item" {
+            let mut cursor = node.walk();
+            let is_mut = node
+                .children(&mut cursor)
+                .any(|child| child.kind() == "mutable_specifier");
+            let mut static_info = StaticInfo {
+                name: field_text(node, "name", code).unwrap_or_default(),
+                is_mut,
+                type_name: field_text(node, "type", code).unwrap_or_default(),
+                value: field_text(node, "value", code),
+                meta: ItemMeta::of_node(node, code, context),
+            };
+            static_info.meta.qualified_path = context.qualified_path(&static_info.name);
+            extracted_data_.statics.push(static_info);
+        }
+        Ok(())
+    }
+
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["static_item"]
+    }
+
+    fn descend(&self) -> bool {
+        false
+    }
+}
+
+pub struct UnionInfoExtractor {}
+
+impl InfoExtractor for UnionInfoExtractor {
+    fn extract(
+        &self,
+        node: Node,
+        code: &str,
+        context: &ExtractContext,
+        extracted_data_: &mut ExtractedData,
+    ) -> Result<(), anyhow::Error> {
+        if node.kind() == "union_item" {
+            let meta = ItemMeta::of_node(node, code, context);
+            let mut union_info = UnionInfo {
+                name: field_text(node, "name", code).unwrap_or_default(),
+                derives: derive_names(&meta.attributes),
+                generics: field_text(node, "type_parameters", code),
+                fields: node
+                    .child_by_field_name("body")
+                    .map(|body| extract_fields(body, code))
+                    .unwrap_or_default(),
+                meta,
+                ..Default::default()
+            };
+            let mut cursor = node.walk();
+            union_info.where_clause = node
+                .children(&mut cursor)
+                .find(|child| child.kind() == "where_clause")
+                .and_then(|where_clause| where_clause.utf8_text(code.as_bytes()).ok())
+                .map(|where_clause| where_clause.to_string());
+            union_info.meta.qualified_path = context.qualified_path(&union_info.name);
+            extracted_data_.unions.push(union_info);
+        }
+        Ok(())
+    }
+
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["union_item"]
+    }
+
+    fn descend(&self) -> bool {
+        false
+    }
+}
+
+pub struct ExternCrateInfoExtractor {}
+
+impl InfoExtractor for ExternCrateInfoExtractor {
+    fn extract(
+        &self,
+        node: Node,
+        code: &str,
+        context: &ExtractContext,
+        extracted_data_: &mut ExtractedData,
+    ) -> Result<(), anyhow::Error> {
+        if node.kind() == "extern_crate_declaration" {
+            let mut extern_crate_info = ExternCrateInfo {
+                name: field_text(node, "name", code).unwrap_or_default(),
+                alias: field_text(node, "alias", code),
+                meta: ItemMeta::of_node(node, code, context),
+            };
+            let bound_name = extern_crate_info
+                .alias
+                .as_ref()
+                .unwrap_or(&extern_crate_info.name);
+            extern_crate_info.meta.qualified_path = context.qualified_path(bound_name);
+            extracted_data_.extern_crates.push(extern_crate_info);
+        }
+        Ok(())
+    }
+
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["extern_crate_declaration"]
+    }
+
+    fn descend(&self) -> bool {
+        false
+    }
+}
+
+/// Collects `extern` blocks with a summary of what they declare. The
+/// declarations are also extracted as `FunctionInfo` and `StaticInfo` by
+/// their own extractors, under the same ids.
+pub struct ForeignModInfoExtractor {}
+
+impl InfoExtractor for ForeignModInfoExtractor {
+    fn extract(
+        &self,
+        node: Node,
+        code: &str,
+        context: &ExtractContext,
+        extracted_data_: &mut ExtractedData,
+    ) -> Result<(), anyhow::Error> {
+        if node.kind() == "foreign_mod_item" {
+            let mut foreign_mod_info = ForeignModInfo {
+                abi: foreign_mod_abi(node, code),
+                meta: ItemMeta {
+                    qualified_path: context.qualified_path(""),
+                    ..ItemMeta::of_node(node, code, context)
+                },
+                ..Default::default()
+            };
+            if let Some(body) = node.child_by_field_name("body") {
+                extract_foreign_items(body, code, context, &mut foreign_mod_info);
+            }
+            extracted_data_.foreign_mods.push(foreign_mod_info);
+        }
+        Ok(())
+    }
+
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["foreign_mod_item"]
+    }
+}
+
+fn extract_foreign_items(
+    body: Node,
+    code: &str,
+    context: &ExtractContext,
+    foreign_mod_info: &mut ForeignModInfo,
+) {
+    let mut cursor = body.walk();
+    for item in body.named_children(&mut cursor) {
+        match item.kind() {
+            "function_signature_item" => {
+                foreign_mod_info.functions.push(ForeignFunctionInfo {
+                    id: context.item_id(item),
+                    name: field_text(item, "name", code).unwrap_or_default(),
+                    signature: declaration_text(item, code),
+                    visibility: Visibility::of_node(item, code),
+                    doc_comment: outer_doc_comment(item, code),
+                    attributes: leading_attributes(item, code),
+                    start_position: item.start_byte(),
+                    end_position: item.end_byte(),
+                });
+            }
+            "static_item" => {
+                let mut static_cursor = item.walk();
+                let is_mut = item
+                    .children(&mut static_cursor)
+                    .any(|child| child.kind() == "mutable_specifier");
+                foreign_mod_info.statics.push(ForeignStaticInfo {
+                    id: context.item_id(item),
+                    name: field_text(item, "name", code).unwrap_or_default(),
+                    type_name: field_text(item, "type", code).unwrap_or_default(),
+                    is_mut,
+                    signature: declaration_text(item, code),
+                    visibility: Visibility::of_node(item, code),
+                    doc_comment: outer_doc_comment(item, code),
+                    attributes: leading_attributes(item, code),
+                    start_position: item.start_byte(),
+                    end_position: item.end_byte(),
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+fn extract_trait_items(
+    body: Node,
+    code: &str,
+    trait_info: &mut TraitInfo,
+) -> Result<(), anyhow::Error> {
+    let mut cursor = body.walk();
+    for item in body.named_children(&mut cursor) {
+        match item.kind() {
+            "function_item" | "function_signature_item" => {
+                let has_default_body = item.kind() == "function_item";
+                // The signature ends where the body starts, or before the `;`.
+                let signature_end = match item.child_by_field_name("body") {
+                    Some(body) => body.start_byte(),
+                    None => item.end_byte(),
+                };
+                let signature = code[item.start_byte()..signature_end]
+                    .trim_end()
+                    .trim_end_matches(';')
+                    .to_string();
+                trait_info.methods.push(TraitMethodInfo {
+                    name: field_text(item, "name", code).unwrap_or_default(),
+                    signature,
+                    has_default_body,
+                    doc_comment: outer_doc_comment(item, code),
+                    attributes: leading_attributes(item, code),
+                });
+            }
+            "associated_type" => {
+                trait_info.associated_types.push(AssociatedTypeInfo {
+                    name: field_text(item, "name", code).unwrap_or_default(),
+                    bounds: item
+                        .child_by_field_name("bounds")
+                        .map(|bounds| bound_list(bounds, code))
+                        .unwrap_or_default(),
+                    doc_comment: outer_doc_comment(item, code),
+                    attributes: leading_attributes(item, code),
+                });
+            }
+            "const_item" => {
+                trait_info.associated_consts.push(AssociatedConstInfo {
+                    name: field_text(item, "name", code).unwrap_or_default(),
+                    type_name: field_text(item, "type", code).unwrap_or_default(),
+                    default_value: field_text(item, "value", code),
+                    doc_comment: outer_doc_comment(item, code),
+                    attributes: leading_attributes(item, code),
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn field_text(node: Node, field: &str, code: &str) -> Option<String> {
+    node.child_by_field_name(field)
+        .and_then(|child| child.utf8_text(code.as_bytes()).ok())
+        .map(|text| text.to_string())
+}
+
+/// The individual bounds of a `trait_bounds` node, e.g. `: Debug + Send`.
+fn bound_list(bounds: Node, code: &str) -> Vec<String> {
+    let mut cursor = bounds.walk();
+    bounds
+        .named_children(&mut cursor)
+        .filter_map(|bound| bound.utf8_text(code.as_bytes()).ok())
+        .map(|bound| bound.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traverse::{traverse_tree, ExtractorTable};
+    use std::path::Path;
+    use tree_sitter::Parser;
+
+    /// The leaves of `declaration`, a single `use` declaration, as
+    /// `(path, alias, is_glob)` with the segments joined by `::`.
+    fn use_leaves(declaration: &str) -> Vec<(String, Option<String>, bool)> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(declaration, None).unwrap();
+        let use_declaration = tree.root_node().named_child(0).unwrap();
+        let argument = use_declaration.child_by_field_name("argument").unwrap();
+        let mut leaves = Vec::new();
+        expand_use_tree(argument, declaration, &[], &mut leaves);
+        leaves
+            .into_iter()
+            .map(|(segments, alias, is_glob, _)| (segments.join("::"), alias, is_glob))
+            .collect()
+    }
+
+    /// Runs every extractor over `code` as if it were a crate root.
+    fn extract(code: &str) -> ExtractedData {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .unwrap();
+        let tree = parser.parse(code, None).unwrap();
+        let boxed_extractors: Vec<Box<dyn InfoExtractor>> = ExtractorKind::all()
+            .iter()
+            .map(|kind| kind.extractor())
+            .collect();
+        let extractors: Vec<&dyn InfoExtractor> =
+            boxed_extractors.iter().map(|e| e.as_ref()).collect();
+        let context = ExtractContext::new(Path::new("/p/src/lib.rs"), CrateContext::default());
+        let mut data = ExtractedData::default();
+        traverse_tree(
+            tree.root_node(),
+            code,
+            &ExtractorTable::new(&extractors),
+            &context,
+            &mut data,
+        );
+        data
+    }
+
+    fn leaf(path: &str) -> (String, Option<String>, bool) {
+        (path.to_string(), None, false)
+    }
+
+    fn segments(path: &str) -> Vec<String> {
+        path.split("::")
+            .map(|segment| segment.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn use_tree_single_path() {
+        assert_eq!(
+            use_leaves("use std::fmt::Debug;"),
+            [leaf("std::fmt::Debug")]
+        );
+        assert_eq!(use_leaves("use serde;"), [leaf("serde")]);
+    }
+
+    #[test]
+    fn use_tree_nested_groups() {
+        assert_eq!(
+            use_leaves("use a::{b, c::{d, e::f}, g::{}};"),
+            [leaf("a::b"), leaf("a::c::d"), leaf("a::c::e::f")]
+        );
+        assert_eq!(
+            use_leaves("use {std::io, core::mem};"),
+            [leaf("std::io"), leaf("core::mem")]
+        );
+    }
+
+    #[test]
+    fn use_tree_self_names_the_group_path() {
+        assert_eq!(
+            use_leaves("use std::io::{self, Read};"),
+            [leaf("std::io"), leaf("std::io::Read")]
+        );
+        assert_eq!(
+            use_leaves("use std::io::{self as stdio};"),
+            [("std::io".to_string(), Some("stdio".to_string()), false)]
+        );
+        // Outside a group `self` is the current module.
+        assert_eq!(
+            use_leaves("use self::shapes::Circle;"),
+            [leaf("self::shapes::Circle")]
+        );
+    }
+
+    #[test]
+    fn use_tree_renames() {
+        assert_eq!(
+            use_leaves("use std::fmt::Result as FmtResult;"),
+            [(
+                "std::fmt::Result".to_string(),
+                Some("FmtResult".to_string()),
+                false
+            )]
+        );
+        assert_eq!(
+            use_leaves("use a::{b as _, c};"),
+            [
+                ("a::b".to_string(), Some("_".to_string()), false),
+                leaf("a::c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn use_tree_globs() {
+        assert_eq!(
+            use_leaves("use bevy::prelude::*;"),
+            [("bevy::prelude".to_string(), None, true)]
+        );
+        assert_eq!(
+            use_leaves("use a::{b::*, c};"),
+            [("a::b".to_string(), None, true), leaf("a::c")]
+        );
+    }
+
+    #[test]
+    fn use_tree_leading_colons() {
+        assert_eq!(use_leaves("use ::std::mem;"), [leaf("::std::mem")]);
+        assert_eq!(
+            use_leaves("use ::{alloc::vec, core::mem};"),
+            [leaf("::alloc::vec"), leaf("::core::mem")]
+        );
+        assert_eq!(path_root(&segments("::std::mem")), PathRoot::Global);
+    }
+
+    #[test]
+    fn use_tree_crate_and_super_prefixes() {
+        assert_eq!(
+            use_leaves("use crate::{config::Config, utils::*};"),
+            [
+                leaf("crate::config::Config"),
+                ("crate::utils".to_string(), None, true)
+            ]
+        );
+        assert_eq!(
+            use_leaves("use super::super::Item;"),
+            [leaf("super::super::Item")]
+        );
+        assert_eq!(path_root(&segments("crate::config")), PathRoot::Crate);
+        assert_eq!(path_root(&segments("$crate::config")), PathRoot::Crate);
+        assert_eq!(path_root(&segments("super::super::Item")), PathRoot::Super);
+        assert_eq!(path_root(&segments("self::shapes")), PathRoot::SelfModule);
+        assert_eq!(path_root(&segments("std::mem")), PathRoot::Name);
+    }
+
+    #[test]
+    fn type_alias_records_the_aliased_type_and_generics() {
+        let data =
+            extract("pub type Bytes = Vec<u8>;\ntype Map<K, V> where K: Ord = BTreeMap<K, V>;\n");
+        let bytes = &data.type_aliases[0];
+        assert_eq!(bytes.name, "Bytes");
+        assert_eq!(bytes.aliased_type, "Vec<u8>");
+        assert_eq!(bytes.generics, None);
+        assert_eq!(bytes.meta.visibility, Visibility::Public);
+
+        let map = &data.type_aliases[1];
+        assert_eq!(map.name, "Map");
+        assert_eq!(map.aliased_type, "BTreeMap<K, V>");
+        assert_eq!(map.generics.as_deref(), Some("<K, V>"));
+        assert_eq!(map.where_clause.as_deref(), Some("where K: Ord"));
+        assert_eq!(map.meta.visibility, Visibility::Private);
+    }
+
+    #[test]
+    fn receivers_are_told_apart_from_parameters() {
+        let data = extract(
+            "impl S {
+                fn by_value(self) {}
+                fn by_mut_value(mut self) {}
+                fn by_ref(&self) {}
+                fn by_mut_ref<'a>(&'a mut self, mut n: u8, (a, b): (u8, u8)) {}
+                fn boxed(self: Box<Self>) {}
+                fn associated(x: u8) {}
+            }",
+        );
+        let receivers: Vec<_> = data
+            .functions
+            .iter()
+            .map(|function| (function.name.as_str(), function.receiver.clone()))
+            .collect();
+        assert_eq!(
+            receivers,
+            [
+                ("by_value", Some(Receiver::Value { is_mut: false })),
+                ("by_mut_value", Some(Receiver::Value { is_mut: true })),
+                ("by_ref", Some(Receiver::Ref { lifetime: None })),
+                (
+                    "by_mut_ref",
+                    Some(Receiver::RefMut {
+                        lifetime: Some("'a".to_string())
+                    })
+                ),
+                (
+                    "boxed",
+                    Some(Receiver::Typed {
+                        is_mut: false,
+                        type_name: "Box<Self>".to_string()
+                    })
+                ),
+                ("associated", None),
+            ]
+        );
+        assert!(data.functions[..5]
+            .iter()
+            .all(|function| function.is_method));
+        assert!(!data.functions[5].is_method);
+
+        let by_mut_ref = &data.functions[3];
+        let parameters: Vec<_> = by_mut_ref
+            .parameters
+            .iter()
+            .map(|p| {
+                (
+                    p.name.as_str(),
+                    p.pattern.as_str(),
+                    p.type_name.as_str(),
+                    p.is_mut,
+                )
+            })
+            .collect();
+        assert_eq!(
+            parameters,
+            [("n", "n", "u8", true), ("", "(a, b)", "(u8, u8)", false)]
+        );
+        assert!(data.functions[4].parameters.is_empty());
+    }
+
+    #[test]
+    fn foreign_functions_are_unsafe_and_may_be_variadic() {
+        let data = extract(
+            "extern \"C\" {
+                fn printf(format: *const c_char, ...) -> c_int;
+                fn vargs(count: usize, args: ...);
+                fn abs(x: c_int) -> c_int;
+            }
+            fn safe() {}",
+        );
+        let printf = &data.functions[0];
+        assert!(printf.is_unsafe);
+        assert!(printf.is_variadic);
+        assert_eq!(printf.abi.as_deref(), Some("C"));
+        let last = printf.parameters.last().unwrap();
+        assert_eq!((last.name.as_str(), last.type_name.as_str()), ("", "..."));
+
+        let vargs = &data.functions[1];
+        assert!(vargs.is_variadic);
+        assert_eq!(vargs.parameters[1].name, "args");
+
+        assert!(data.functions[2].is_unsafe);
+        assert!(!data.functions[2].is_variadic);
+        assert!(!data.functions[3].is_unsafe);
+    }
+
+    /// `(name, type_name, visibility)` of each field.
+    fn field_summary(fields: &[FieldInfo]) -> Vec<(&str, &str, Visibility)> {
+        fields
+            .iter()
+            .map(|field| {
+                (
+                    field.name.as_str(),
+                    field.type_name.as_str(),
+                    field.visibility.clone(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn structs_record_their_shape_generics_and_fields() {
+        let data = extract(
+            "/// A player
+#[derive(Debug, Clone)]
+pub struct Player<'a, T: Clone> where T: Default {
+    /// The name
+    pub name: &'a str,
+    pub(crate) score: T,
+    #[serde(skip)]
+    secret: u8,
+}
+pub(crate) struct Velocity(pub f32, #[allow(dead_code)] pub(super) u8, String);
+struct Marker;
+",
+        );
+        let player = &data.structs[0];
+        assert_eq!(player.name, "Player");
+        assert_eq!(player.shape, StructShape::Named);
+        assert_eq!(player.generics.as_deref(), Some("<'a, T: Clone>"));
+        assert_eq!(player.where_clause.as_deref(), Some("where T: Default"));
+        assert_eq!(player.derives, ["Debug", "Clone"]);
+        assert_eq!(player.meta.visibility, Visibility::Public);
+        assert_eq!(player.meta.doc_comment.as_ref().unwrap().text, "A player");
+        assert_eq!(
+            field_summary(&player.fields),
+            [
+                ("name", "&'a str", Visibility::Public),
+                ("score", "T", Visibility::Crate),
+                ("secret", "u8", Visibility::Private),
+            ]
+        );
+        assert_eq!(
+            player.fields[0].doc_comment.as_ref().unwrap().text,
+            "The name"
+        );
+        assert_eq!(player.fields[2].attributes[0].path, "serde");
+
+        let velocity = &data.structs[1];
+        assert_eq!(velocity.shape, StructShape::Tuple);
+        assert_eq!(velocity.meta.visibility, Visibility::Crate);
+        assert_eq!(
+            field_summary(&velocity.fields),
+            [
+                ("0", "f32", Visibility::Public),
+                ("1", "u8", Visibility::Super),
+                ("2", "String", Visibility::Private),
+            ]
+        );
+        assert_eq!(velocity.fields[1].attributes[0].path, "allow");
+        // A tuple field starts at its visibility, after its attributes.
+        let field_prefix = "pub(crate) struct Velocity(pub f32, #[allow(dead_code)] ";
+        assert_eq!(
+            velocity.fields[1].start_position,
+            velocity.meta.start_position + field_prefix.len()
+        );
+
+        let marker = &data.structs[2];
+        assert_eq!(marker.shape, StructShape::Unit);
+        assert!(marker.fields.is_empty());
+        assert_eq!(marker.generics, None);
+    }
+
+    #[test]
+    fn enums_record_discriminants_and_variant_fields() {
+        let data = extract(
+            "#[derive(Default)]
+pub enum Shape<T> where T: Copy {
+    /// Nothing to draw
+    #[default]
+    Empty,
+    Point(T, pub(crate) T),
+    Rect { width: T, height: Vec<T> },
+}
+enum Flag {
+    A = 1,
+    B = 1 << 3,
+}
+",
+        );
+        let shape = &data.enums[0];
+        assert_eq!(shape.name, "Shape");
+        assert_eq!(shape.generics.as_deref(), Some("<T>"));
+        assert_eq!(shape.where_clause.as_deref(), Some("where T: Copy"));
+        assert_eq!(shape.derives, ["Default"]);
+        let shapes: Vec<_> = shape
+            .variants
+            .iter()
+            .map(|variant| (variant.name.as_str(), variant.shape))
+            .collect();
+        assert_eq!(
+            shapes,
+            [
+                ("Empty", StructShape::Unit),
+                ("Point", StructShape::Tuple),
+                ("Rect", StructShape::Named),
+            ]
+        );
+
+        let empty = &shape.variants[0];
+        assert!(empty.is_default);
+        assert!(empty.fields.is_empty());
+        assert_eq!(empty.doc_comment.as_ref().unwrap().text, "Nothing to draw");
+        assert_eq!(
+            field_summary(&shape.variants[1].fields),
+            [
+                ("0", "T", Visibility::Private),
+                ("1", "T", Visibility::Crate)
+            ]
+        );
+        assert_eq!(
+            field_summary(&shape.variants[2].fields),
+            [
+                ("width", "T", Visibility::Private),
+                ("height", "Vec<T>", Visibility::Private),
+            ]
+        );
+        assert!(!shape.variants[2].is_default);
+
+        let discriminants: Vec<_> = data.enums[1]
+            .variants
+            .iter()
+            .map(|variant| variant.discriminant.as_deref())
+            .collect();
+        assert_eq!(discriminants, [Some("1"), Some("1 << 3")]);
+        assert_eq!(shape.variants[0].discriminant, None);
     }
 }
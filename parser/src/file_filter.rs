@@ -0,0 +1,155 @@
+// src/file_filter.rs
+use std::{
+    fs::File,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use ignore::WalkBuilder;
+
+use crate::config::Config;
+
+/// Project-specific ignore file, read with the same syntax as `.gitignore`.
+pub const RAG_IGNORE_FILE: &str = ".ragignore";
+
+/// Number of leading lines searched for the `@generated` marker.
+const GENERATED_MARKER_LINES: usize = 5;
+
+/// Decides which `.rs` files under a root get indexed.
+///
+/// Globs are matched against the path relative to the root, e.g.
+/// `src/stepping.rs`. On top of the globs the walker honors `.gitignore`,
+/// `.ignore` and `.ragignore` files, skips directories by name, and drops
+/// files that are marked `@generated` or are larger than the size limit.
+pub struct FileFilter {
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+    ignore_directories: Vec<String>,
+    respect_ignore_files: bool,
+    skip_generated: bool,
+    max_file_size: Option<u64>,
+}
+
+impl FileFilter {
+    pub fn from_config(config: &Config) -> Result<Self> {
+        let include = if config.include.is_empty() {
+            None
+        } else {
+            Some(build_glob_set(&config.include)?)
+        };
+        Ok(Self {
+            include,
+            exclude: build_glob_set(&config.exclude)?,
+            ignore_directories: config.ignore_directories.clone(),
+            respect_ignore_files: config.respect_ignore_files,
+            skip_generated: config.skip_generated,
+            max_file_size: config.max_file_size,
+        })
+    }
+
+    pub fn is_selected(&self, relative_path: &Path) -> bool {
+        if self.exclude.is_match(relative_path) {
+            return false;
+        }
+        match &self.include {
+            Some(include) => include.is_match(relative_path),
+            None => true,
+        }
+    }
+
+    /// Walks `root_dir` and returns every `.rs` file that passes the filter,
+    /// sorted by path.
+    pub fn collect_rust_files(&self, root_dir: &Path) -> Result<Vec<PathBuf>> {
+        let mut builder = WalkBuilder::new(root_dir);
+        builder
+            .git_ignore(self.respect_ignore_files)
+            .git_exclude(self.respect_ignore_files)
+            .git_global(self.respect_ignore_files)
+            .ignore(self.respect_ignore_files)
+            .parents(self.respect_ignore_files)
+            .require_git(false)
+            .sort_by_file_name(|a, b| a.cmp(b));
+        if self.respect_ignore_files {
+            builder.add_custom_ignore_filename(RAG_IGNORE_FILE);
+        }
+
+        let ignore_directories = self.ignore_directories.clone();
+        builder.filter_entry(move |entry| {
+            let is_ignored_directory = entry.depth() > 0
+                && entry.file_type().is_some_and(|ft| ft.is_dir())
+                && ignore_directories
+                    .iter()
+                    .any(|name| entry.file_name() == name.as_str());
+            if is_ignored_directory {
+                println!("Ignoring directory: {}", entry.path().display());
+            }
+            !is_ignored_directory
+        });
+
+        let mut files = Vec::new();
+        for entry in builder.build() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(e) => {
+                    eprintln!("Failed to read directory entry: {}", e);
+                    continue;
+                }
+            };
+            let path = entry.path();
+            let is_rust_file = entry.file_type().is_some_and(|ft| ft.is_file())
+                && path.extension().is_some_and(|ext| ext == "rs");
+            if !is_rust_file {
+                continue;
+            }
+
+            let relative_path = path.strip_prefix(root_dir).unwrap_or(path);
+            if !self.is_selected(relative_path) {
+                println!("Skipping file: {}", path.display());
+                continue;
+            }
+            if let Some(max_file_size) = self.max_file_size {
+                let size = entry
+                    .metadata()
+                    .with_context(|| format!("Failed to read metadata for '{}'", path.display()))?
+                    .len();
+                if size > max_file_size {
+                    println!("Skipping large file ({} bytes): {}", size, path.display());
+                    continue;
+                }
+            }
+            if self.skip_generated && is_generated(path)? {
+                println!("Skipping generated file: {}", path.display());
+                continue;
+            }
+            files.push(path.to_path_buf());
+        }
+        Ok(files)
+    }
+}
+
+/// Checks the first few lines of a file for the `@generated` marker.
+fn is_generated(path: &Path) -> Result<bool> {
+    let file =
+        File::open(path).with_context(|| format!("Failed to read file '{}'", path.display()))?;
+    for line in BufReader::new(file).lines().take(GENERATED_MARKER_LINES) {
+        // A read error here usually means the file is not valid UTF-8, which the
+        // parser will report properly later on.
+        let Ok(line) = line else {
+            break;
+        };
+        if line.contains("@generated") {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+fn build_glob_set(patterns: &[String]) -> Result<GlobSet> {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        builder.add(Glob::new(pattern).with_context(|| format!("Invalid glob '{}'", pattern))?);
+    }
+    Ok(builder.build()?)
+}
@@ -1,67 +1,141 @@
+mod attributes;
+mod cache;
+mod cli;
+mod config;
+mod crates;
 mod debug;
+mod diagnostics;
+mod docs;
 mod extract;
+mod file_filter;
+mod modules;
+mod query_extract;
+mod ron_compat;
 mod traverse;
 mod utils;
+mod visibility;
+mod watch;
 
 use crate::{
+    cache::{cache_fingerprint, ExtractionCache},
+    cli::{Cli, Command, IndexArgs, DEFAULT_QUERY_DIR},
+    config::Config,
     extract::*,
-    traverse::{traverse_and_parse_directory, InfoExtractor},
-    utils::print_extracted_stats::print_extracted_stats,
+    file_filter::FileFilter,
+    modules::resolve_module_tree,
+    query_extract::{compare_query_items, load_query_extractors, query_sources_hash},
+    traverse::{
+        traverse_and_count_node_kinds, traverse_and_parse_directory, InfoExtractor, NodeKindCensus,
+    },
+    utils::{
+        print_diagnostics::print_diagnostics, print_extracted_stats::print_extracted_stats,
+        print_node_census::print_node_census, print_orphan_files::print_orphan_files,
+        print_query_comparison::print_query_comparison, show_items::show_items,
+    },
+    visibility::resolve_effective_visibility,
+    watch::watch,
 };
 
 #[cfg(feature = "print_blocks")]
 use crate::utils::print_blocks::{print_blocks, print_single_block};
 
-const TARGET_DIR: &str = "../example_traverse_target/src";
-
-use anyhow::Result;
-use std::{any::Any, env, path::Path};
+use anyhow::{bail, Context, Result};
+use clap::Parser as _;
+use std::{
+    any::Any,
+    env, fs,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tree_sitter::Parser;
 
 #[allow(unused_imports)]
-use debug::{process_any_debug, process_box_take_ownership};
+use debug::{print_syntax_tree, process_any_debug, process_box_take_ownership};
 mod saver;
 
-use saver::save_extracted_data;
+use saver::{load_extracted_data, save_output};
 
 fn main() -> Result<()> {
-    // Count node kinds
+    let cli = Cli::parse();
+
+    match cli.command {
+        Command::Index(args) => run_index(args),
+        Command::Watch(args) => {
+            let config = args.index.into_config()?;
+            let boxed_extractors = build_extractors(&config)?;
+            let extractors: Vec<&dyn InfoExtractor> =
+                boxed_extractors.iter().map(|e| e.as_ref()).collect();
+            watch(&config, extractors, Duration::from_millis(args.debounce_ms))
+        }
+        Command::Census(args) => {
+            let config = args.index.into_config()?;
+            let file_filter = FileFilter::from_config(&config)?;
+            let boxed_extractors = build_extractors(&config)?;
+            let extractors: Vec<&dyn InfoExtractor> =
+                boxed_extractors.iter().map(|e| e.as_ref()).collect();
+            let mut census = NodeKindCensus::default();
+            for root_directory in &config.roots {
+                let root_census = traverse_and_count_node_kinds(
+                    root_directory,
+                    &file_filter,
+                    extractors.clone(),
+                    args.include_anonymous,
+                )?;
+                census.merge(root_census);
+            }
+            print_node_census(&census, args.by_file, args.by_parent);
+            Ok(())
+        }
+        Command::CompareQueries(args) => compare_queries(args),
+        Command::Stats { input } => {
+            let extracted_data = load_extracted_data(&input)?;
+            println!("Extracted data loaded from {}", input.display());
+            print_extracted_stats(&extracted_data);
+            Ok(())
+        }
+        Command::Show { name, input } => {
+            let extracted_data = load_extracted_data(&input)?;
+            if show_items(&extracted_data, &name) == 0 {
+                println!("No items named '{}' found in {}", name, input.display());
+            }
+            Ok(())
+        }
+        Command::DumpTree { file } => dump_tree(&file),
+    }
+}
+
+fn run_index(args: IndexArgs) -> Result<()> {
     println!("Current directory: {:?}", env::current_dir()?);
-    let root_directory = Path::new(TARGET_DIR);
+    let config = args.into_config()?;
 
-    let directories_to_ignore = Some(vec!["examples".to_string(), "assets".to_string()]);
+    let file_filter = FileFilter::from_config(&config)?;
 
     // Create extractors
-    let struct_extractor = StructInfoExtractor {};
-    let function_extractor = FunctionInfoExtractor {};
-    let type_alias_extractor = TypeAliasInfoExtractor {};
-    let impl_extractor = ImplInfoExtractor {};
-    let use_dependency_extractor = UseDependencyInfoExtractor {};
-    let mod_extractor = ModInfoExtractor {};
-    let enum_extractor = EnumInfoExtractor {};
-    let macro_extractor = MacroInfoExtractor {};
-
-    let extractors: Vec<&dyn InfoExtractor> = vec![
-        &struct_extractor,
-        &function_extractor,
-        &type_alias_extractor,
-        &impl_extractor,
-        &use_dependency_extractor,
-        &mod_extractor,
-        &enum_extractor,
-        &macro_extractor,
-    ];
-
-    // Traverse the directory and extract information
-    let extracted_data =
-        traverse_and_parse_directory(root_directory, directories_to_ignore, extractors.clone())?;
-
-    // Ensure the 'data' directory exists
-    let output_dir = env::current_dir()?.join("data");
-    if !output_dir.exists() {
-        std::fs::create_dir_all(&output_dir)?;
+    let boxed_extractors = build_extractors(&config)?;
+    let extractors: Vec<&dyn InfoExtractor> = boxed_extractors.iter().map(|e| e.as_ref()).collect();
+
+    let mut cache = if config.cache.enabled {
+        let fingerprint =
+            cache_fingerprint(&config.extractors, &query_sources_hash(&config.queries)?);
+        ExtractionCache::load(&config.cache.path, fingerprint)
+    } else {
+        ExtractionCache::disabled()
+    };
+    let mut extracted_data = index_roots(&config, &file_filter, &extractors, &mut cache)?;
+    if config.cache.enabled {
+        cache.save(&config.cache.path)?;
+    }
+
+    print_diagnostics(&extracted_data.diagnostics);
+    print_orphan_files(&extracted_data.orphan_files);
+    if config.strict && !extracted_data.diagnostics.is_empty() {
+        bail!(
+            "{} syntax errors found, not saving output (strict mode)",
+            extracted_data.diagnostics.len()
+        );
     }
 
-    let output_file_path = output_dir.join("extracted_data.ron");
+    let output_file_path = config.output.path.clone();
     println!("Output file path: {}", output_file_path.display());
 
     // Read the code from the file
@@ -70,10 +144,163 @@ fn main() -> Result<()> {
     #[cfg(feature = "print_blocks")]
     print_blocks(&extracted_data);
 
-    save_extracted_data(&extracted_data, &output_file_path)?;
-    print_extracted_stats(&extracted_data, &output_file_path);
+    extracted_data.config = config;
+    save_output(&extracted_data, &extracted_data.config)?;
+    println!("Extracted data saved to {}", output_file_path.display());
+    print_extracted_stats(&extracted_data);
 
+    println!(
+        "Files re-extracted: {}, reused from cache: {}, removed since last run: {}",
+        cache.files_extracted,
+        cache.files_reused,
+        cache.files_removed()
+    );
     println!("Directory parsing complete.");
 
     Ok(())
 }
+
+/// Traverses each root directory on a pool of `config.threads` workers and
+/// resolves the cross-file links between the extracted items.
+fn index_roots(
+    config: &Config,
+    file_filter: &FileFilter,
+    extractors: &[&dyn InfoExtractor],
+    cache: &mut ExtractionCache,
+) -> Result<ExtractedData> {
+    let thread_pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(config.threads.unwrap_or(0))
+        .build()?;
+    let mut extracted_data = ExtractedData::default();
+    for root_directory in &config.roots {
+        extracted_data.merge(thread_pool.install(|| {
+            traverse_and_parse_directory(root_directory, file_filter, extractors.to_vec(), cache)
+        })?);
+    }
+    extracted_data.link_macro_invocations();
+    resolve_module_tree(&mut extracted_data);
+    resolve_effective_visibility(&mut extracted_data);
+    Ok(extracted_data)
+}
+
+fn build_extractors(config: &Config) -> Result<Vec<Box<dyn InfoExtractor>>> {
+    let mut extractors: Vec<Box<dyn InfoExtractor>> = config
+        .extractors
+        .iter()
+        .map(|kind| kind.extractor())
+        .collect();
+    for query_extractor in load_query_extractors(&config.queries)? {
+        extractors.push(Box::new(query_extractor));
+    }
+    Ok(extractors)
+}
+
+fn compare_queries(args: IndexArgs) -> Result<()> {
+    let mut config = args.into_config()?;
+    if config.queries.is_empty() {
+        config.queries = vec![PathBuf::from(DEFAULT_QUERY_DIR)];
+    }
+    let file_filter = FileFilter::from_config(&config)?;
+    let boxed_extractors = build_extractors(&config)?;
+    let extractors: Vec<&dyn InfoExtractor> = boxed_extractors.iter().map(|e| e.as_ref()).collect();
+
+    // Both kinds of extractors see exactly the same files in the same pass.
+    let mut cache = ExtractionCache::disabled();
+    let mut extracted_data = ExtractedData::default();
+    for root_directory in &config.roots {
+        extracted_data.merge(traverse_and_parse_directory(
+            root_directory,
+            &file_filter,
+            extractors.clone(),
+            &mut cache,
+        )?);
+    }
+    let query_items = std::mem::take(&mut extracted_data.query_items);
+    print_query_comparison(&compare_query_items(&extracted_data, &query_items));
+    Ok(())
+}
+
+fn dump_tree(file: &Path) -> Result<()> {
+    let code = fs::read_to_string(file)
+        .with_context(|| format!("Failed to read file '{}'", file.display()))?;
+    let mut parser = Parser::new();
+    parser
+        .set_language(&tree_sitter_rust::LANGUAGE.into())
+        .context("Error loading Rust grammar")?;
+    let tree = parser
+        .parse(&code, None)
+        .with_context(|| format!("Parsing failed for file: {}", file.display()))?;
+    print_syntax_tree(tree.root_node(), &code, 0);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index_with_threads(threads: usize) -> String {
+        let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let config = Config {
+            roots: vec![
+                manifest_dir.join("../example_traverse_target"),
+                manifest_dir.join("src"),
+            ],
+            threads: Some(threads),
+            ..Config::default()
+        };
+        let file_filter = FileFilter::from_config(&config).unwrap();
+        let boxed_extractors = build_extractors(&config).unwrap();
+        let extractors: Vec<&dyn InfoExtractor> =
+            boxed_extractors.iter().map(|e| e.as_ref()).collect();
+        let mut cache = ExtractionCache::disabled();
+        let mut extracted_data =
+            index_roots(&config, &file_filter, &extractors, &mut cache).unwrap();
+        extracted_data.config = config;
+        ron::ser::to_string_pretty(&extracted_data, Default::default()).unwrap()
+    }
+
+    /// Runs `index` over a package with a syntax error, with `flag` added.
+    /// Returns the result and whether the output was saved.
+    fn index_broken_package(flag: &str) -> (Result<()>, bool) {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("src")).unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "fn broken( {}\n").unwrap();
+        let config_path = dir.path().join("rag.toml");
+        fs::write(&config_path, "").unwrap();
+        let output_path = dir.path().join("out.ron");
+        let args = [
+            "parser",
+            "index",
+            dir.path().to_str().unwrap(),
+            "--config",
+            config_path.to_str().unwrap(),
+            "--no-cache",
+            "-o",
+            output_path.to_str().unwrap(),
+            flag,
+        ];
+        let Command::Index(args) = Cli::try_parse_from(args).unwrap().command else {
+            panic!("not parsed as index");
+        };
+        (run_index(args), output_path.exists())
+    }
+
+    #[test]
+    fn strict_mode_fails_on_syntax_errors_without_saving() {
+        let (result, saved) = index_broken_package("--strict");
+        let error = result.unwrap_err().to_string();
+        assert!(error.contains("strict mode"), "{}", error);
+        assert!(!saved);
+
+        let (result, saved) = index_broken_package("--no-strict");
+        result.unwrap();
+        assert!(saved);
+    }
+
+    #[test]
+    fn output_does_not_depend_on_thread_count() {
+        let single_threaded = index_with_threads(1);
+        assert!(single_threaded.contains("index_roots"));
+        assert_eq!(single_threaded, index_with_threads(8));
+    }
+}
@@ -0,0 +1,528 @@
+// src/modules.rs
+use std::{
+    collections::{BTreeMap, BTreeSet, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::crates::CrateContext;
+use crate::extract::{ExtractedData, ModInfo, ModInfoExtractor};
+use crate::traverse::{traverse_tree, with_rust_parser, ExtractContext, ExtractorTable};
+
+/// The modules of one crate, starting at its root file.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ModuleTree {
+    pub crate_name: String,
+    pub crate_root: String,
+    pub root: ModuleNode,
+}
+
+impl ModuleTree {
+    /// Every file the crate's modules are written in, starting at the root.
+    pub fn file_paths(&self) -> Vec<&str> {
+        let mut file_paths = Vec::new();
+        let mut pending = vec![&self.root];
+        while let Some(node) = pending.pop() {
+            if !node.is_inline {
+                file_paths.extend(node.file_path.as_deref());
+            }
+            pending.extend(&node.children);
+        }
+        file_paths
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ModuleNode {
+    pub name: String,
+    pub qualified_path: String,
+    /// Id of the `mod` item, `None` for the crate root
+    pub mod_id: Option<String>,
+    pub is_inline: bool,
+    /// File the module's items are written in. `None` when no file was found
+    /// for a `mod name;` declaration.
+    pub file_path: Option<String>,
+    pub children: Vec<ModuleNode>,
+}
+
+/// Walks the `mod` declarations of every crate from its root file, resolving
+/// each `mod name;` to `name.rs` or `name/mod.rs`, or to its `#[path]`.
+struct ModuleResolver<'a> {
+    /// Modules declared directly in a file, outside any inline module
+    top_level_mods: HashMap<&'a str, Vec<&'a ModInfo>>,
+    /// Modules declared inside an inline module, by the id of that module
+    nested_mods: HashMap<&'a str, Vec<&'a ModInfo>>,
+    /// File of every resolved out-of-line module, by module id
+    resolved: HashMap<String, String>,
+    /// The module each reached file holds, by file
+    file_modules: HashMap<String, FileModule>,
+}
+
+/// The module whose items a file holds, as resolved from the `mod`
+/// declarations rather than from where the file sits on disk.
+#[derive(Clone)]
+struct FileModule {
+    qualified_path: String,
+    /// Id of the `mod name;` declaring the file, `None` for a crate root
+    mod_id: Option<String>,
+    /// Root file of the crate that reached the file
+    crate_root: String,
+}
+
+impl<'a> ModuleResolver<'a> {
+    fn new(mods: &'a [ModInfo]) -> Self {
+        let inline_ids: BTreeSet<&str> = mods
+            .iter()
+            .filter(|item| item.is_inline)
+            .map(|item| item.meta.id.as_str())
+            .collect();
+        let mut resolver = ModuleResolver {
+            top_level_mods: HashMap::new(),
+            nested_mods: HashMap::new(),
+            resolved: HashMap::new(),
+            file_modules: HashMap::new(),
+        };
+        for item in mods {
+            match item.meta.parent_id.as_deref() {
+                Some(parent_id) if inline_ids.contains(parent_id) => resolver
+                    .nested_mods
+                    .entry(parent_id)
+                    .or_default()
+                    .push(item),
+                // Modules in function bodies and the like are kept with the
+                // file, since their files resolve the same way.
+                _ => resolver
+                    .top_level_mods
+                    .entry(item.meta.file_path.as_str())
+                    .or_default()
+                    .push(item),
+            }
+        }
+        resolver
+    }
+
+    /// The modules declared in `file_path`, which holds the module `module`.
+    /// `child_dir` is where the files of its `mod name;` declarations are
+    /// looked up.
+    fn file_children(
+        &mut self,
+        file_path: &str,
+        child_dir: &Path,
+        module: FileModule,
+    ) -> Vec<ModuleNode> {
+        if self.file_modules.contains_key(file_path) {
+            // Two declarations pointing at the same file; only walk it once.
+            return Vec::new();
+        }
+        self.file_modules
+            .insert(file_path.to_string(), module.clone());
+        let file_dir = Path::new(file_path)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mods = self
+            .top_level_mods
+            .get(file_path)
+            .cloned()
+            .unwrap_or_default();
+        mods.into_iter()
+            .map(|item| self.module_node(item, &module, &file_dir, child_dir))
+            .collect()
+    }
+
+    /// `file_module` is the module of the file the declaration is written in.
+    /// `path_dir` is what a `#[path]` on the
+    /// declaration is relative to, and `child_dir` is where `name.rs` and
+    /// `name/mod.rs` are looked up.
+    fn module_node(
+        &mut self,
+        item: &'a ModInfo,
+        file_module: &FileModule,
+        path_dir: &Path,
+        child_dir: &Path,
+    ) -> ModuleNode {
+        let disk_module_path = item
+            .meta
+            .crate_context
+            .qualified_module_path(Path::new(&item.meta.file_path));
+        let mut node = ModuleNode {
+            name: item.name.clone(),
+            qualified_path: rebase_path(
+                &item.meta.qualified_path,
+                &disk_module_path,
+                &file_module.qualified_path,
+            )
+            .unwrap_or_else(|| item.meta.qualified_path.clone()),
+            mod_id: Some(item.meta.id.clone()),
+            is_inline: item.is_inline,
+            ..Default::default()
+        };
+
+        if item.is_inline {
+            // Both lookups continue in a directory named after the module.
+            let nested_dir = child_dir.join(&item.name);
+            let mods = self
+                .nested_mods
+                .get(item.meta.id.as_str())
+                .cloned()
+                .unwrap_or_default();
+            node.file_path = Some(item.meta.file_path.clone());
+            node.children = mods
+                .into_iter()
+                .map(|nested| self.module_node(nested, file_module, &nested_dir, &nested_dir))
+                .collect();
+            return node;
+        }
+
+        let candidates = match &item.path_attribute {
+            Some(path) => vec![path_dir.join(path)],
+            None => vec![
+                child_dir.join(format!("{}.rs", item.name)),
+                child_dir.join(&item.name).join("mod.rs"),
+            ],
+        };
+        let Some(file) = candidates.into_iter().find(|candidate| candidate.is_file()) else {
+            return node;
+        };
+        let file = file.canonicalize().unwrap_or(file);
+        // `name/mod.rs` and `#[path]` files own their directory, like a crate
+        // root; `name.rs` keeps its submodules in `name/`.
+        let nested_dir = if item.path_attribute.is_some()
+            || file.file_name().is_some_and(|name| name == "mod.rs")
+        {
+            file.parent().map(Path::to_path_buf).unwrap_or_default()
+        } else {
+            child_dir.join(&item.name)
+        };
+        let file_path = file.display().to_string();
+        self.resolved
+            .insert(item.meta.id.clone(), file_path.clone());
+        let module = FileModule {
+            qualified_path: node.qualified_path.clone(),
+            mod_id: Some(item.meta.id.clone()),
+            crate_root: file_module.crate_root.clone(),
+        };
+        node.children = self.file_children(&file_path, &nested_dir, module);
+        node.file_path = Some(file_path);
+        node
+    }
+}
+
+/// Builds the module tree of every crate whose root file was indexed, sets
+/// `ModInfo::resolved_file` on each `mod name;` whose file was found, and
+/// lists the indexed files of those crates that their root does not reach as
+/// `orphan_files`. The items of every reached file get the crate context,
+/// qualified path and parent of the module they were reached as.
+///
+/// Call once after all files are merged, since a module is declared in a
+/// different file than its own.
+pub fn resolve_module_tree(data: &mut ExtractedData) {
+    // `file_contents` also holds each file under the path it was walked as;
+    // items refer to files by their absolute path.
+    let indexed_files: BTreeSet<&String> = data
+        .file_contents
+        .keys()
+        .filter(|file_path| Path::new(file_path).is_absolute())
+        .collect();
+
+    // Targets without a manifest entry, such as examples and tests, are known
+    // from the crate roots their items were indexed with.
+    let mut crate_roots: BTreeMap<String, String> = BTreeMap::new();
+    for crate_info in &data.crates {
+        for root in crate_info.lib_root.iter().chain(&crate_info.bin_roots) {
+            crate_roots.insert(root.clone(), crate_info.target_name(root));
+        }
+    }
+    // The crate each file was indexed as part of, by file.
+    let mut file_crate_roots: HashMap<&str, &str> = HashMap::new();
+    for item in data.items() {
+        let meta = item.meta();
+        let crate_context = &meta.crate_context;
+        if !crate_context.crate_root.is_empty() {
+            crate_roots
+                .entry(crate_context.crate_root.clone())
+                .or_insert_with(|| crate_context.crate_name.clone());
+            file_crate_roots.insert(&meta.file_path, &crate_context.crate_root);
+        }
+    }
+
+    let mut resolver = ModuleResolver::new(&data.mods);
+    let mut module_trees = Vec::new();
+    for (crate_root, crate_name) in crate_roots.clone() {
+        if !indexed_files.contains(&crate_root) {
+            continue;
+        }
+        let root_dir = Path::new(&crate_root)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let qualified_path = match crate_name.as_str() {
+            "" => "crate".to_string(),
+            crate_name => crate_name.to_string(),
+        };
+        let module = FileModule {
+            qualified_path: qualified_path.clone(),
+            mod_id: None,
+            crate_root: crate_root.clone(),
+        };
+        let children = resolver.file_children(&crate_root, &root_dir, module);
+        module_trees.push(ModuleTree {
+            root: ModuleNode {
+                name: crate_name.clone(),
+                qualified_path,
+                file_path: Some(crate_root.clone()),
+                children,
+                ..Default::default()
+            },
+            crate_name,
+            crate_root,
+        });
+    }
+
+    let resolved = std::mem::take(&mut resolver.resolved);
+    let file_modules = std::mem::take(&mut resolver.file_modules);
+    // Without an indexed root nothing of a crate is reached, so only files of
+    // crates with a tree can be orphans. Files without items have no known
+    // crate and are left out as well.
+    let tree_roots: BTreeSet<&str> = module_trees
+        .iter()
+        .map(|tree| tree.crate_root.as_str())
+        .collect();
+    let orphan_files = indexed_files
+        .into_iter()
+        .filter(|file_path| !file_modules.contains_key(*file_path))
+        .filter(|file_path| {
+            file_crate_roots
+                .get(file_path.as_str())
+                .is_some_and(|crate_root| tree_roots.contains(crate_root))
+        })
+        .cloned()
+        .collect();
+    for item in &mut data.mods {
+        item.resolved_file = resolved.get(&item.meta.id).cloned();
+    }
+    data.module_trees = module_trees;
+    data.orphan_files = orphan_files;
+
+    // Items were given the crate and module path of where their file sits on
+    // disk. Move the items of every reached file into the crate that reached
+    // it and under its resolved module, and make that module's `mod`
+    // declaration the parent of its top-level items.
+    for item in data.items_mut() {
+        let meta = item.meta_mut();
+        let Some(module) = file_modules.get(&meta.file_path) else {
+            continue;
+        };
+        let disk_module_path = meta
+            .crate_context
+            .qualified_module_path(Path::new(&meta.file_path));
+        if meta.crate_context.crate_root != module.crate_root {
+            meta.crate_context = CrateContext {
+                crate_name: crate_roots[&module.crate_root].clone(),
+                crate_root: module.crate_root.clone(),
+                ..meta.crate_context.clone()
+            };
+        }
+        if let Some(qualified_path) = rebase_path(
+            &meta.qualified_path,
+            &disk_module_path,
+            &module.qualified_path,
+        ) {
+            meta.qualified_path = qualified_path;
+        }
+        if meta.parent_id.is_none() {
+            meta.parent_id = module.mod_id.clone();
+        }
+    }
+}
+
+/// Files reached from `crate_root` through `mod` declarations, the root
+/// included, by the same rules as `resolve_module_tree`.
+///
+/// Used to tell which crate a file belongs to before anything is extracted,
+/// so every reached file is read and parsed here for its `mod` items only.
+/// Unreadable files are kept as reached but declare nothing.
+pub fn reachable_files(crate_root: &Path) -> BTreeSet<PathBuf> {
+    let extractor = ModInfoExtractor {};
+    let extractors = ExtractorTable::new(&[&extractor]);
+    let crate_root = crate_root.display().to_string();
+    let root_dir = Path::new(&crate_root)
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let mut mods = Vec::new();
+    let mut parsed: BTreeSet<String> = BTreeSet::new();
+    loop {
+        // Walk what is known so far; newly reached files may declare more.
+        let unparsed: Vec<String> = {
+            let mut resolver = ModuleResolver::new(&mods);
+            let module = FileModule {
+                qualified_path: "crate".to_string(),
+                mod_id: None,
+                crate_root: crate_root.clone(),
+            };
+            resolver.file_children(&crate_root, &root_dir, module);
+            resolver
+                .file_modules
+                .into_keys()
+                .filter(|file_path| !parsed.contains(file_path))
+                .collect()
+        };
+        if unparsed.is_empty() {
+            return parsed.into_iter().map(PathBuf::from).collect();
+        }
+        for file_path in unparsed {
+            if let Ok(code) = fs::read_to_string(&file_path) {
+                let context = ExtractContext::new(Path::new(&file_path), CrateContext::default());
+                let mut data = ExtractedData::default();
+                if let Ok(Some(tree)) = with_rust_parser(|parser| Ok(parser.parse(&code, None))) {
+                    traverse_tree(tree.root_node(), &code, &extractors, &context, &mut data);
+                }
+                mods.extend(data.mods);
+            }
+            parsed.insert(file_path);
+        }
+    }
+}
+
+/// `path` with its leading module path `from` replaced by `to`. Paths of trait
+/// impls and their items start with `<`, e.g. `<from::Type as Trait>::method`.
+/// `None` when `path` is not inside `from`.
+fn rebase_path(path: &str, from: &str, to: &str) -> Option<String> {
+    let (opening, path) = match path.strip_prefix('<') {
+        Some(path) => ("<", path),
+        None => ("", path),
+    };
+    let rest = path.strip_prefix(from)?;
+    if !rest.is_empty() && !rest.starts_with("::") && !rest.starts_with(' ') {
+        return None;
+    }
+    Some(format!("{}{}{}", opening, to, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::ExtractionCache;
+    use crate::config::Config;
+    use crate::extract::{ExtractorKind, FunctionInfo};
+    use crate::file_filter::FileFilter;
+    use crate::traverse::{traverse_and_parse_directory, InfoExtractor};
+
+    /// Indexes a package made of `files` and resolves its module tree.
+    fn index_package(files: &[(&str, &str)]) -> (tempfile::TempDir, ExtractedData) {
+        let dir = tempfile::tempdir().unwrap();
+        for (file_path, contents) in files {
+            let file_path = dir.path().join(file_path);
+            fs::create_dir_all(file_path.parent().unwrap()).unwrap();
+            fs::write(file_path, contents).unwrap();
+        }
+        let config = Config {
+            roots: vec![dir.path().to_path_buf()],
+            ..Config::default()
+        };
+        let file_filter = FileFilter::from_config(&config).unwrap();
+        let boxed_extractors: Vec<Box<dyn InfoExtractor>> = ExtractorKind::all()
+            .iter()
+            .map(|kind| kind.extractor())
+            .collect();
+        let extractors = boxed_extractors.iter().map(|e| e.as_ref()).collect();
+        let mut cache = ExtractionCache::disabled();
+        let mut data =
+            traverse_and_parse_directory(dir.path(), &file_filter, extractors, &mut cache).unwrap();
+        resolve_module_tree(&mut data);
+        (dir, data)
+    }
+
+    fn function<'a>(data: &'a ExtractedData, name: &str) -> &'a FunctionInfo {
+        data.functions
+            .iter()
+            .find(|function| function.name == name)
+            .unwrap()
+    }
+
+    fn mod_id(data: &ExtractedData, name: &str) -> String {
+        data.mods
+            .iter()
+            .find(|item| item.name == name)
+            .map(|item| item.meta.id.clone())
+            .unwrap()
+    }
+
+    #[test]
+    fn resolves_sibling_files_and_their_directories() {
+        let (dir, data) = index_package(&[
+            ("Cargo.toml", "[package]\nname = \"p\"\n"),
+            ("src/lib.rs", "pub mod foo;\n"),
+            ("src/foo.rs", "pub mod bar;\n"),
+            ("src/foo/bar.rs", "pub fn deep() {}\n"),
+        ]);
+        let src_dir = dir.path().canonicalize().unwrap().join("src");
+
+        let tree = &data.module_trees[0];
+        let foo = &tree.root.children[0];
+        let bar = &foo.children[0];
+        assert_eq!(foo.qualified_path, "p::foo");
+        assert_eq!(bar.qualified_path, "p::foo::bar");
+        assert_eq!(
+            bar.file_path.as_deref(),
+            Some(src_dir.join("foo/bar.rs").display().to_string().as_str())
+        );
+
+        let deep = function(&data, "deep");
+        assert_eq!(deep.meta.qualified_path, "p::foo::bar::deep");
+        assert_eq!(deep.meta.parent_id, Some(mod_id(&data, "bar")));
+        assert!(data.orphan_files.is_empty());
+    }
+
+    #[test]
+    fn follows_path_attributes() {
+        let (dir, data) = index_package(&[
+            ("Cargo.toml", "[package]\nname = \"p\"\n"),
+            (
+                "src/lib.rs",
+                "#[path = \"impl_detail.rs\"]\npub mod secret;\n",
+            ),
+            ("src/impl_detail.rs", "pub fn leak() {}\n"),
+        ]);
+        let src_dir = dir.path().canonicalize().unwrap().join("src");
+
+        let secret = data.mods.iter().find(|item| item.name == "secret").unwrap();
+        assert_eq!(
+            secret.resolved_file.as_deref(),
+            Some(
+                src_dir
+                    .join("impl_detail.rs")
+                    .display()
+                    .to_string()
+                    .as_str()
+            )
+        );
+        let leak = function(&data, "leak");
+        assert_eq!(leak.meta.qualified_path, "p::secret::leak");
+        assert_eq!(leak.meta.parent_id, Some(secret.meta.id.clone()));
+        assert!(data.orphan_files.is_empty());
+    }
+
+    #[test]
+    fn reached_files_take_the_crate_of_the_root_that_reached_them() {
+        let (dir, data) = index_package(&[
+            ("Cargo.toml", "[package]\nname = \"p\"\n"),
+            ("src/lib.rs", ""),
+            ("src/util.rs", "pub fn helper() {}\n"),
+            ("tests/t.rs", "#[path = \"../src/util.rs\"]\nmod util;\n"),
+        ]);
+        let package_dir = dir.path().canonicalize().unwrap();
+
+        let helper = function(&data, "helper");
+        assert_eq!(
+            helper.meta.crate_context.crate_root,
+            package_dir.join("tests/t.rs").display().to_string()
+        );
+        assert_eq!(helper.meta.crate_context.crate_name, "t");
+        assert_eq!(helper.meta.qualified_path, "t::util::helper");
+        assert!(data.orphan_files.is_empty());
+    }
+}
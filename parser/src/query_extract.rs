@@ -0,0 +1,418 @@
+// src/query_extract.rs
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Node, Query, QueryCursor};
+
+use crate::extract::{ExtractedData, ItemMeta, QueryItemInfo};
+use crate::traverse::{enclosing_scopes, ExtractContext, InfoExtractor};
+use crate::visibility::Visibility;
+
+/// Extension of the tree-sitter query files picked up from a query directory.
+const QUERY_FILE_EXTENSION: &str = "scm";
+
+/// Capture every pattern must have; its node is the extracted item.
+const ITEM_CAPTURE: &str = "item";
+
+/// An extractor defined by a tree-sitter query file instead of Rust code.
+///
+/// Every match of the query becomes one `QueryItemInfo`, tagged with the stem
+/// of the query file as its kind. So `queries/function.scm` yields items of
+/// kind `function`.
+pub struct QueryExtractor {
+    item_kind: String,
+    query: Query,
+    capture_names: Vec<String>,
+    item_capture: u32,
+}
+
+impl QueryExtractor {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read query file '{}'", path.display()))?;
+        let item_kind = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .with_context(|| format!("Query file '{}' has no name", path.display()))?;
+        let query = Query::new(&tree_sitter_rust::LANGUAGE.into(), &source)
+            .with_context(|| format!("Invalid query file '{}'", path.display()))?;
+        let Some(item_capture) = query.capture_index_for_name(ITEM_CAPTURE) else {
+            bail!(
+                "Query file '{}' has no @{} capture",
+                path.display(),
+                ITEM_CAPTURE
+            );
+        };
+        let capture_names = query
+            .capture_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        Ok(QueryExtractor {
+            item_kind,
+            query,
+            capture_names,
+            item_capture,
+        })
+    }
+
+    fn item_from_match(
+        &self,
+        captures: &[tree_sitter::QueryCapture],
+        code: &str,
+        context: &ExtractContext,
+    ) -> Option<QueryItemInfo> {
+        let item_node = captures
+            .iter()
+            .find(|capture| capture.index == self.item_capture)?
+            .node;
+        // The query runs once from the root, so the walk's scope stack is
+        // empty here; rebuild it from the item's ancestors.
+        let context = &ExtractContext {
+            scopes: enclosing_scopes(item_node, code, context),
+            ..context.clone()
+        };
+        let mut item = QueryItemInfo {
+            item_kind: self.item_kind.clone(),
+            meta: ItemMeta::at_node(item_node, context),
+            ..Default::default()
+        };
+        for capture in captures {
+            let text = node_text(capture.node, code);
+            match self.capture_names[capture.index as usize].as_str() {
+                ITEM_CAPTURE => {}
+                "name" => item.name = text,
+                "visibility" => item.meta.visibility = Visibility::parse(&text),
+                "params" => {
+                    let mut cursor = capture.node.walk();
+                    item.params = capture
+                        .node
+                        .named_children(&mut cursor)
+                        .filter(|param| !param.is_extra())
+                        .map(|param| node_text(param, code))
+                        .collect();
+                }
+                "body" => {
+                    item.body_start_position = Some(capture.node.start_byte());
+                    item.body_end_position = Some(capture.node.end_byte());
+                }
+                other => item
+                    .captures
+                    .entry(other.to_string())
+                    .or_default()
+                    .push(text),
+            }
+        }
+        item.meta.qualified_path = context.qualified_path(&item.name);
+        Some(item)
+    }
+}
+
+impl InfoExtractor for QueryExtractor {
+    fn extract(
+        &self,
+        node: Node,
+        code: &str,
+        context: &ExtractContext,
+        extracted_data_: &mut ExtractedData,
+    ) -> Result<(), anyhow::Error> {
+        let mut cursor = QueryCursor::new();
+        let mut matches = cursor.matches(&self.query, node, code.as_bytes());
+        while let Some(query_match) = matches.next() {
+            if let Some(item) = self.item_from_match(query_match.captures, code, context) {
+                extracted_data_.query_items.push(item);
+            }
+        }
+        Ok(())
+    }
+
+    // The query is run once over the whole file.
+    fn node_kinds(&self) -> &'static [&'static str] {
+        &["source_file"]
+    }
+
+    fn descend(&self) -> bool {
+        false
+    }
+}
+
+fn node_text(node: Node, code: &str) -> String {
+    node.utf8_text(code.as_bytes())
+        .map(|text| text.to_string())
+        .unwrap_or_default()
+}
+
+/// Expands the configured query paths into query files. Directories
+/// contribute their `.scm` files in name order.
+pub fn query_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let mut dir_files: Vec<PathBuf> = fs::read_dir(path)
+            .with_context(|| format!("Failed to read query directory '{}'", path.display()))?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|file| {
+                file.extension()
+                    .is_some_and(|ext| ext == QUERY_FILE_EXTENSION)
+            })
+            .collect();
+        dir_files.sort();
+        files.extend(dir_files);
+    }
+    Ok(files)
+}
+
+pub fn load_query_extractors(paths: &[PathBuf]) -> Result<Vec<QueryExtractor>> {
+    query_files(paths)?
+        .iter()
+        .map(|file| {
+            println!("Loading query: {}", file.display());
+            QueryExtractor::from_file(file)
+        })
+        .collect()
+}
+
+/// Hash of the contents of every configured query file, so cached results
+/// are dropped when a query is edited.
+pub fn query_sources_hash(paths: &[PathBuf]) -> Result<String> {
+    let mut hasher = blake3::Hasher::new();
+    for file in query_files(paths)? {
+        let source = fs::read(&file)
+            .with_context(|| format!("Failed to read query file '{}'", file.display()))?;
+        hasher.update(file.display().to_string().as_bytes());
+        hasher.update(&source);
+    }
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Items found by the hand-written extractors and the query extractors for
+/// one item kind, keyed by file, span and name.
+#[derive(Debug, Default)]
+pub struct QueryComparison {
+    pub item_kind: String,
+    pub handwritten_count: usize,
+    pub query_count: usize,
+    pub only_handwritten: Vec<ItemKey>,
+    pub only_query: Vec<ItemKey>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ItemKey {
+    pub file_path: String,
+    pub start_position: usize,
+    pub end_position: usize,
+    pub name: String,
+}
+
+/// Compares the items of `handwritten` with the query items of the same kind.
+/// Kinds only one side knows about are reported with an empty other side.
+pub fn compare_query_items(
+    handwritten: &ExtractedData,
+    query_items: &[QueryItemInfo],
+) -> Vec<QueryComparison> {
+    let mut handwritten_keys = handwritten_item_keys(handwritten);
+    let mut query_keys: BTreeMap<String, Vec<ItemKey>> = BTreeMap::new();
+    for item in query_items {
+        query_keys
+            .entry(item.item_kind.clone())
+            .or_default()
+            .push(ItemKey {
+                file_path: item.meta.file_path.clone(),
+                start_position: item.meta.start_position,
+                end_position: item.meta.end_position,
+                name: item.name.clone(),
+            });
+    }
+
+    let mut kinds: Vec<String> = handwritten_keys.keys().cloned().collect();
+    kinds.extend(query_keys.keys().cloned());
+    kinds.sort();
+    kinds.dedup();
+
+    kinds
+        .into_iter()
+        .map(|kind| {
+            let mut expected = handwritten_keys.remove(&kind).unwrap_or_default();
+            let mut found = query_keys.remove(&kind).unwrap_or_default();
+            expected.sort();
+            found.sort();
+            QueryComparison {
+                handwritten_count: expected.len(),
+                query_count: found.len(),
+                only_handwritten: difference(&expected, &found),
+                only_query: difference(&found, &expected),
+                item_kind: kind,
+            }
+        })
+        .collect()
+}
+
+/// Items in `a` that are not in `b`, counting duplicates. Both must be sorted.
+fn difference(a: &[ItemKey], b: &[ItemKey]) -> Vec<ItemKey> {
+    let mut remaining = b.iter().peekable();
+    let mut missing = Vec::new();
+    for key in a {
+        while remaining.next_if(|other| *other < key).is_some() {}
+        if remaining.next_if(|other| *other == key).is_none() {
+            missing.push(key.clone());
+        }
+    }
+    missing
+}
+
+fn handwritten_item_keys(data: &ExtractedData) -> BTreeMap<String, Vec<ItemKey>> {
+    let mut keys: BTreeMap<String, Vec<ItemKey>> = BTreeMap::new();
+    // Use declarations and foreign blocks have no name, so they are compared
+    // by span only.
+    for item in data.items() {
+        let Some(kind) = item.extractor_kind() else {
+            continue;
+        };
+        keys.entry(kind.name().to_string())
+            .or_default()
+            .push(ItemKey {
+                file_path: item.meta().file_path.clone(),
+                start_position: item.meta().start_position,
+                end_position: item.meta().end_position,
+                name: item.name().to_string(),
+            });
+    }
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crates::CrateContext;
+    use crate::extract::{ExtractedItem, ExtractorKind};
+    use crate::traverse::{traverse_tree, with_rust_parser, ExtractorTable};
+
+    /// At least one item of every kind the bundled queries extract.
+    const FIXTURE: &str = r#"extern crate alloc as a;
+use std::{fmt, io::Read as _};
+
+/// A point
+#[derive(Debug)]
+pub struct Point<T> { pub x: T, y: T }
+struct Unit;
+pub(crate) enum Shape { Circle(f32), Empty }
+union Bits { int: u32, float: f32 }
+type Pair = (u8, u8);
+const MAX: u8 = 3;
+static mut COUNT: u32 = 0;
+
+pub trait Draw {
+    fn draw(&self);
+    fn name(&self) -> &str { "shape" }
+}
+
+impl<T> Point<T> {
+    pub fn new(x: T, y: T) -> Self { Point { x, y } }
+}
+
+impl Draw for Unit {
+    fn draw(&self) { println!("unit"); }
+}
+
+macro_rules! square { ($x:expr) => { $x * $x }; }
+
+extern "C" {
+    fn abs(x: i32) -> i32;
+    static errno: i32;
+}
+
+mod inner {
+    pub mod nested {
+        pub fn deep() -> u8 { square!(2) }
+    }
+}
+"#;
+
+    /// Runs the hand-written extractors and the bundled queries over `code`.
+    fn extract_both(code: &str) -> ExtractedData {
+        let queries = [Path::new(env!("CARGO_MANIFEST_DIR")).join("queries")];
+        let query_extractors = load_query_extractors(&queries).unwrap();
+        let handwritten: Vec<Box<dyn InfoExtractor>> = ExtractorKind::all()
+            .iter()
+            .map(|kind| kind.extractor())
+            .collect();
+        let extractors: Vec<&dyn InfoExtractor> = handwritten
+            .iter()
+            .map(|extractor| extractor.as_ref())
+            .chain(query_extractors.iter().map(|q| q as &dyn InfoExtractor))
+            .collect();
+        let tree = with_rust_parser(|parser| Ok(parser.parse(code, None)))
+            .unwrap()
+            .unwrap();
+        let context = ExtractContext::new(
+            Path::new("/p/src/lib.rs"),
+            CrateContext {
+                crate_name: "p".to_string(),
+                ..Default::default()
+            },
+        );
+        let mut data = ExtractedData::default();
+        traverse_tree(
+            tree.root_node(),
+            code,
+            &ExtractorTable::new(&extractors),
+            &context,
+            &mut data,
+        );
+        data
+    }
+
+    #[test]
+    fn bundled_queries_find_what_the_extractors_find() {
+        let mut data = extract_both(FIXTURE);
+        let query_items = std::mem::take(&mut data.query_items);
+        let comparisons = compare_query_items(&data, &query_items);
+
+        let query_kinds = query_files(&[Path::new(env!("CARGO_MANIFEST_DIR")).join("queries")])
+            .unwrap()
+            .len();
+        assert_eq!(comparisons.len(), query_kinds);
+        for comparison in &comparisons {
+            assert!(
+                comparison.handwritten_count > 0,
+                "no {} in the fixture",
+                comparison.item_kind
+            );
+            assert!(
+                comparison.only_handwritten.is_empty() && comparison.only_query.is_empty(),
+                "{:?}",
+                comparison
+            );
+        }
+    }
+
+    #[test]
+    fn query_items_share_paths_and_visibility_with_extracted_items() {
+        let data = extract_both(FIXTURE);
+        for function in &data.functions {
+            let query_item = data
+                .query_items
+                .iter()
+                .find(|item| item.item_kind == "function" && item.meta.id == function.meta.id)
+                .unwrap();
+            assert_eq!(query_item.name(), function.name);
+            assert_eq!(query_item.meta.qualified_path, function.meta.qualified_path);
+            assert_eq!(query_item.meta.parent_id, function.meta.parent_id);
+        }
+        let deep = data
+            .query_items
+            .iter()
+            .find(|item| item.name == "deep")
+            .unwrap();
+        assert_eq!(deep.meta.qualified_path, "p::inner::nested::deep");
+        assert_eq!(deep.meta.visibility, Visibility::Public);
+    }
+}
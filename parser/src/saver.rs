@@ -1,12 +1,44 @@
+use crate::config::{Config, OutputFormat};
 use crate::extract::ExtractedData;
-use anyhow::Result;
+use crate::ron_compat;
+use anyhow::{Context, Result};
 use ron::ser::PrettyConfig;
 use std::{fs::File, io::Write, path::Path};
 
-pub fn save_extracted_data(extracted: &ExtractedData, output_file_path: &Path) -> Result<()> {
-    let ron_string = ron::ser::to_string_pretty(&extracted, PrettyConfig::default())?;
+pub fn save_extracted_data(
+    extracted: &ExtractedData,
+    output_file_path: &Path,
+    format: OutputFormat,
+) -> Result<()> {
+    let output_string = match format {
+        OutputFormat::Ron => ron::ser::to_string_pretty(&extracted, PrettyConfig::default())?,
+        OutputFormat::Json => serde_json::to_string_pretty(&extracted)?,
+    };
 
-    let mut file = File::create(&output_file_path)?;
-    file.write_all(ron_string.as_bytes())?;
+    let mut file = File::create(output_file_path)?;
+    file.write_all(output_string.as_bytes())?;
     Ok(())
 }
+
+/// Saves `extracted` to the output path and format configured in `config`,
+/// creating the output directory if needed.
+pub fn save_output(extracted: &ExtractedData, config: &Config) -> Result<()> {
+    let output_file_path = config.output.path.as_path();
+    if let Some(output_dir) = output_file_path.parent() {
+        if !output_dir.as_os_str().is_empty() && !output_dir.exists() {
+            std::fs::create_dir_all(output_dir)?;
+        }
+    }
+    save_extracted_data(extracted, output_file_path, config.output.format)
+}
+
+pub fn load_extracted_data(input_file_path: &Path) -> Result<ExtractedData> {
+    let input_string = std::fs::read_to_string(input_file_path)
+        .with_context(|| format!("Failed to read file '{}'", input_file_path.display()))?;
+    let extracted = match OutputFormat::from_path(input_file_path) {
+        OutputFormat::Ron => ron_compat::from_str(&input_string).map_err(anyhow::Error::from),
+        OutputFormat::Json => serde_json::from_str(&input_string).map_err(anyhow::Error::from),
+    }
+    .with_context(|| format!("Failed to parse '{}'", input_file_path.display()))?;
+    Ok(extracted)
+}
@@ -1,204 +1,697 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::{fs, path::Path};
 
-use tree_sitter::{Node, Parser};
-use walkdir::WalkDir;
+use rayon::prelude::*;
+use tree_sitter::{Node, Parser, Tree};
 
 use anyhow::{Context, Result};
 
+use crate::attributes::inner_attributes;
+use crate::cache::{content_hash, CacheEntry, ExtractionCache};
+use crate::crates::{CrateContext, CrateIndex};
+use crate::diagnostics::{
+    collect_syntax_diagnostics, mark_items_in_error_regions, SyntaxDiagnostic,
+};
+use crate::docs::inner_doc_comment;
 use crate::extract::ExtractedData;
+use crate::file_filter::FileFilter;
 
-// Define a trait for extraction
-pub trait InfoExtractor {
+/// Information about the file being extracted that every item is tagged with.
+#[derive(Clone, Debug, Default)]
+pub struct ExtractContext {
+    pub file_path: String,
+    pub crate_context: CrateContext,
+    /// Module path of the file inside its crate, e.g. `["ui", "button"]`
+    pub module_path: Vec<String>,
+    /// Items enclosing the node being extracted, outermost first
+    pub scopes: Vec<Scope>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ScopeKind {
+    /// An inline `mod name { ... }`
+    Module,
+    /// An `impl` block; `trait_name` is set for trait impls
+    Impl {
+        trait_name: Option<String>,
+    },
+    Trait,
+    Function,
+}
+
+/// An item that encloses other items, pushed while the walk is inside it.
+#[derive(Clone, Debug)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    /// Module, trait or function name, or the self type of an impl
+    pub name: String,
+    pub item_id: String,
+}
+
+impl ExtractContext {
+    pub fn new(file_path: &Path, crate_context: CrateContext) -> Self {
+        ExtractContext {
+            file_path: file_path.display().to_string(),
+            module_path: crate_context.module_path(file_path),
+            crate_context,
+            scopes: Vec::new(),
+        }
+    }
+
+    /// Id of the item starting at `node`, unique across the index.
+    pub fn item_id(&self, node: Node) -> String {
+        format!("{}@{}:{}", node.kind(), self.file_path, node.start_byte())
+    }
+
+    /// Id of the innermost enclosing item, if any.
+    pub fn parent_id(&self) -> Option<String> {
+        self.scopes.last().map(|scope| scope.item_id.clone())
+    }
+
+    /// Fully qualified path of an item named `name` in the current scope, e.g.
+    /// `breakout::stepping::SteppingPlugin::new`. Methods of trait impls are
+    /// written `<crate::Type as Trait>::method`. An empty `name` yields the
+    /// path of the scope itself.
+    pub fn qualified_path(&self, name: &str) -> String {
+        let crate_name = match self.crate_context.crate_name.as_str() {
+            "" => "crate",
+            crate_name => crate_name,
+        };
+        let mut path = crate_name.to_string();
+        for segment in &self.module_path {
+            path = format!("{}::{}", path, segment);
+        }
+        for scope in &self.scopes {
+            path = match &scope.kind {
+                ScopeKind::Impl {
+                    trait_name: Some(trait_name),
+                } => format!("<{}::{} as {}>", path, scope.name, trait_name),
+                _ => format!("{}::{}", path, scope.name),
+            };
+        }
+        if name.is_empty() {
+            path
+        } else {
+            format!("{}::{}", path, name)
+        }
+    }
+}
+
+/// The scopes enclosing `node`, found by walking up its ancestors. Used by
+/// extractors that are not called from inside the walk, such as queries.
+pub fn enclosing_scopes(node: Node, code: &str, context: &ExtractContext) -> Vec<Scope> {
+    let mut scopes = Vec::new();
+    let mut current = node.parent();
+    while let Some(ancestor) = current {
+        scopes.extend(scope_for(ancestor, code, context));
+        current = ancestor.parent();
+    }
+    scopes.reverse();
+    scopes
+}
+
+/// The scope `node` opens for its children, if it is a module, impl, trait
+/// or function.
+fn scope_for(node: Node, code: &str, context: &ExtractContext) -> Option<Scope> {
+    let field_text = |field: &str| {
+        node.child_by_field_name(field)
+            .and_then(|child| child.utf8_text(code.as_bytes()).ok())
+            .map(|text| text.to_string())
+    };
+    let (kind, name) = match node.kind() {
+        "mod_item" => (ScopeKind::Module, field_text("name")?),
+        "impl_item" => (
+            ScopeKind::Impl {
+                trait_name: field_text("trait"),
+            },
+            field_text("type")?,
+        ),
+        "trait_item" => (ScopeKind::Trait, field_text("name")?),
+        "function_item" => (ScopeKind::Function, field_text("name")?),
+        _ => return None,
+    };
+    Some(Scope {
+        kind,
+        name,
+        item_id: context.item_id(node),
+    })
+}
+
+// Define a trait for extraction. Extractors are shared between the worker
+// threads that parse files in parallel, so they must be `Sync`.
+pub trait InfoExtractor: Sync {
     fn extract(
         &self,
         node: Node,
         code: &str,
-        file_path: String,
+        context: &ExtractContext,
         extracted_data_: &mut ExtractedData,
     ) -> Result<(), anyhow::Error>;
-    fn node_kind(&self) -> &'static str;
+    /// Node kinds this extractor is called for.
+    fn node_kinds(&self) -> &'static [&'static str];
+    /// Whether this extractor should still be called for nodes inside a node it
+    /// matched. Returning `false` skips the subtree for this extractor only;
+    /// the other extractors keep walking it.
+    fn descend(&self) -> bool {
+        true
+    }
+}
+
+/// Extractors grouped by the node kinds they registered for, so the walk does
+/// a single lookup per node instead of asking every extractor.
+pub struct ExtractorTable<'a> {
+    // Each extractor is stored with its position in the original list, which
+    // indexes the per-subtree `inactive` flags in `traverse_tree`.
+    by_kind: HashMap<&'static str, Vec<(usize, &'a dyn InfoExtractor)>>,
+    len: usize,
+}
+
+impl<'a> ExtractorTable<'a> {
+    pub fn new(extractors: &[&'a dyn InfoExtractor]) -> Self {
+        let mut by_kind: HashMap<&'static str, Vec<(usize, &'a dyn InfoExtractor)>> =
+            HashMap::new();
+        for (index, extractor) in extractors.iter().enumerate() {
+            for kind in extractor.node_kinds() {
+                by_kind.entry(kind).or_default().push((index, *extractor));
+            }
+        }
+        ExtractorTable {
+            by_kind,
+            len: extractors.len(),
+        }
+    }
+
+    fn for_kind(&self, kind: &str) -> &[(usize, &'a dyn InfoExtractor)] {
+        self.by_kind.get(kind).map_or(&[], |extractors| extractors)
+    }
+
+    /// Every node kind at least one extractor registered for.
+    pub fn kinds(&self) -> impl Iterator<Item = &'static str> + '_ {
+        self.by_kind.keys().copied()
+    }
 }
 
 pub fn traverse_tree(
     node: Node,
     code: &str,
-    extractors: &[&dyn InfoExtractor], // Use a slice of trait objects
-    file_path: String,
+    extractors: &ExtractorTable,
+    context: &ExtractContext,
     extracted_data_: &mut ExtractedData,
-    node_kinds: &mut HashSet<String>, // Collect node kinds
 ) {
-    for extractor in extractors {
-        if node.kind() == extractor.node_kind() {
-            extract_results(node, code, extractors, &file_path, extracted_data_);
-        }
+    let inactive = vec![false; extractors.len];
+    let mut context = context.clone();
+    traverse_node(
+        node,
+        code,
+        extractors,
+        &inactive,
+        &mut context,
+        extracted_data_,
+    );
+}
+
+/// `inactive` flags the extractors that matched an ancestor of `node` and do
+/// not descend, so they are not called again inside it. Modules, impls,
+/// traits and functions are pushed onto `context.scopes` while their
+/// children are walked.
+fn traverse_node(
+    node: Node,
+    code: &str,
+    extractors: &ExtractorTable,
+    inactive: &[bool],
+    context: &mut ExtractContext,
+    extracted_data_: &mut ExtractedData,
+) {
+    let child_inactive =
+        extract_results(node, code, extractors, inactive, context, extracted_data_);
+    let inactive = child_inactive.as_deref().unwrap_or(inactive);
+    // Nothing left to find below this node.
+    if inactive.iter().all(|&is_inactive| is_inactive) {
+        return;
     }
 
+    let scope = scope_for(node, code, context);
+    let pushed_scope = scope.is_some();
+    context.scopes.extend(scope);
+
     // Recursively traverse children
     let mut cursor = node.walk();
     if cursor.goto_first_child() {
         loop {
-            traverse_tree(
+            traverse_node(
                 cursor.node(),
                 code,
                 extractors,
-                file_path.clone(),
+                inactive,
+                context,
                 extracted_data_,
-                node_kinds,
             );
             if !cursor.goto_next_sibling() {
                 break;
             }
         }
     }
+
+    if pushed_scope {
+        context.scopes.pop();
+    }
 }
 
+/// Runs the active extractors registered for the kind of `node`. Returns the
+/// flags for the children when a matching extractor does not descend.
 fn extract_results(
     node: Node<'_>,
     code: &str,
-    extractors: &[&dyn InfoExtractor],
-    file_path: &String,
+    extractors: &ExtractorTable,
+    inactive: &[bool],
+    context: &ExtractContext,
     extracted_data_: &mut ExtractedData,
-) {
-    // Recursively traverse children, but only if the current node wasn't already extracted
-    // This prevents us from recursing too deeply after we've found a struct, function, etc.
-    // let mut extracted = false;
-    for extractor in extractors {
-        if node.kind() == extractor.node_kind() {
-            if let Err(e) = extractor.extract(node, code, file_path.clone(), extracted_data_) {
-                eprintln!("Failed to extract info: {}", e);
+) -> Option<Vec<bool>> {
+    let mut child_inactive: Option<Vec<bool>> = None;
+    for &(index, extractor) in extractors.for_kind(node.kind()) {
+        if inactive[index] {
+            continue;
+        }
+        if let Err(e) = extractor.extract(node, code, context, extracted_data_) {
+            eprintln!("Failed to extract info: {}", e);
+        }
+        if !extractor.descend() {
+            child_inactive.get_or_insert_with(|| inactive.to_vec())[index] = true;
+        }
+    }
+    child_inactive
+}
+
+/// Histogram of tree-sitter node kinds across a target.
+#[derive(Debug, Default)]
+pub struct NodeKindCensus {
+    /// Count of every node kind over all files
+    pub totals: BTreeMap<String, usize>,
+    /// Node kind counts for each file
+    pub per_file: BTreeMap<String, BTreeMap<String, usize>>,
+    /// For each parent kind, how often each child kind appears directly under it
+    pub per_parent: BTreeMap<String, BTreeMap<String, usize>>,
+    /// Kinds that at least one of the given extractors is registered for
+    pub handled: BTreeSet<String>,
+}
+
+impl NodeKindCensus {
+    /// Adds the counts of `other`, e.g. the census of another root, to `self`.
+    pub fn merge(&mut self, other: NodeKindCensus) {
+        for (kind, count) in other.totals {
+            *self.totals.entry(kind).or_default() += count;
+        }
+        self.per_file.extend(other.per_file);
+        for (parent, children) in other.per_parent {
+            let parent_counts = self.per_parent.entry(parent).or_default();
+            for (kind, count) in children {
+                *parent_counts.entry(kind).or_default() += count;
             }
-            // extracted = true;
-            // break;
-        }
-    }
-
-    // if !extracted {
-    //     let mut cursor = node.walk();
-    //     if cursor.goto_first_child() {
-    //         loop {
-    //             extract_results(node, code, extractors, &file_path, extracted_data_);
-    //             traverse_tree(
-    //                 cursor.node(),
-    //                 code,
-    //                 extractors,
-    //                 file_path.clone(),
-    //                 extracted_data_,
-    //                 node_kinds,
-    //             );
-    //             if !cursor.goto_next_sibling() {
-    //                 break;
-    //             }
-    //         }
-    //     }
-    // }
-}
-#[allow(dead_code)]
+        }
+        self.handled.extend(other.handled);
+    }
+
+    pub fn is_handled(&self, kind: &str) -> bool {
+        self.handled.contains(kind)
+    }
+}
+
+/// Counts the node kinds of every file under `root_dir`. Anonymous nodes such
+/// as punctuation and keywords are only counted with `include_anonymous`.
 pub fn traverse_and_count_node_kinds(
     root_dir: &Path,
-    ignored_directories: Option<Vec<String>>,
-    _extractors: Vec<&dyn InfoExtractor>,
-) -> Result<HashMap<String, usize>> {
-    let node_kind_counts: HashMap<String, usize> = HashMap::new();
-
-    for entry in WalkDir::new(root_dir).into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        let entry_name = entry.file_name().to_string_lossy();
-
-        if let Some(ref ignore_list) = ignored_directories {
-            if entry.depth() > 0
-                && entry.file_type().is_dir()
-                && ignore_list.contains(&entry_name.to_string())
-            {
-                continue;
+    file_filter: &FileFilter,
+    extractors: Vec<&dyn InfoExtractor>,
+    include_anonymous: bool,
+) -> Result<NodeKindCensus> {
+    let mut census = NodeKindCensus {
+        handled: ExtractorTable::new(&extractors)
+            .kinds()
+            .map(|kind| kind.to_string())
+            .collect(),
+        ..Default::default()
+    };
+
+    let files = file_filter.collect_rust_files(root_dir)?;
+    let file_censuses = files
+        .par_iter()
+        .map(|path| {
+            let code = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read file '{}'", path.display()))?;
+            let tree = with_rust_parser(|parser| Ok(parser.parse(&code, None)))?
+                .with_context(|| format!("Parsing failed for file: {}", path.display()))?;
+            Ok((
+                path.display().to_string(),
+                count_node_kinds(tree.root_node(), include_anonymous),
+            ))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for (file_path, mut file_census) in file_censuses {
+        file_census
+            .per_file
+            .insert(file_path, file_census.totals.clone());
+        census.merge(file_census);
+    }
+    Ok(census)
+}
+
+fn count_node_kinds(root_node: Node, include_anonymous: bool) -> NodeKindCensus {
+    let mut census = NodeKindCensus::default();
+    let mut cursor = root_node.walk();
+    // Kinds of the nodes above the cursor, innermost last.
+    let mut parents: Vec<&'static str> = Vec::new();
+
+    loop {
+        let node = cursor.node();
+        if node.is_named() || include_anonymous {
+            *census.totals.entry(node.kind().to_string()).or_default() += 1;
+            if let Some(parent) = parents.last() {
+                *census
+                    .per_parent
+                    .entry(parent.to_string())
+                    .or_default()
+                    .entry(node.kind().to_string())
+                    .or_default() += 1;
             }
         }
 
-        if path.is_file() && path.extension().map_or(false, |ext| ext == "rs") {
-            let code = fs::read_to_string(path)?;
-            let mut parser = Parser::new();
-            parser
-                .set_language(&tree_sitter_rust::LANGUAGE.into())
-                .context("Error loading Rust grammar")?;
-            let tree = parser.parse(&code, None).unwrap();
-            let root_node = tree.root_node();
-            let mut node_kinds: HashSet<String> = HashSet::new();
-            traverse_tree(
-                root_node,
-                &code,
-                &[],
-                "".to_string(),
-                &mut ExtractedData::default(),
-                &mut node_kinds,
-            );
+        if cursor.goto_first_child() {
+            parents.push(node.kind());
+            continue;
+        }
+        while !cursor.goto_next_sibling() {
+            if !cursor.goto_parent() {
+                return census;
+            }
+            parents.pop();
         }
     }
-    Ok(node_kind_counts)
 }
 
+thread_local! {
+    // Each worker thread keeps one parser for every file it handles.
+    static RUST_PARSER: RefCell<Option<Parser>> = const { RefCell::new(None) };
+}
+
+/// Runs `f` with this thread's Rust parser, creating it on first use.
+pub fn with_rust_parser<T>(f: impl FnOnce(&mut Parser) -> Result<T>) -> Result<T> {
+    RUST_PARSER.with(|cell| {
+        let mut slot = cell.borrow_mut();
+        let parser = match slot.as_mut() {
+            Some(parser) => parser,
+            None => {
+                let mut parser = Parser::new();
+                parser
+                    .set_language(&tree_sitter_rust::LANGUAGE.into())
+                    .context("Error loading Rust grammar")?;
+                slot.insert(parser)
+            }
+        };
+        f(parser)
+    })
+}
+
+/// Parses the files under `root_dir` in parallel on the current rayon pool.
+///
+/// Every file is extracted into its own `ExtractedData`, and the partial
+/// results are merged in path order, so the output does not depend on how
+/// many threads ran or which one finished first. Files whose contents match
+/// an entry in `cache` are not parsed again.
 pub fn traverse_and_parse_directory(
     root_dir: &Path,
-    ignored_directories: Option<Vec<String>>,
+    file_filter: &FileFilter,
     extractors: Vec<&dyn InfoExtractor>,
+    cache: &mut ExtractionCache,
 ) -> Result<ExtractedData> {
     let mut all_results = ExtractedData::default();
+    let crate_index = CrateIndex::discover(root_dir)?;
+    all_results.crates = crate_index.crates().cloned().collect();
 
-    all_results.file_contents = std::collections::HashMap::new();
-    for entry in WalkDir::new(root_dir).into_iter().filter_map(|e| e.ok()) {
-        let path = entry.path();
-        let entry_name = entry.file_name().to_string_lossy();
-
-        if let Some(ref ignore_list) = ignored_directories {
-            if entry.depth() > 0
-                && entry.file_type().is_dir()
-                && ignore_list.contains(&entry_name.to_string())
-            {
-                println!("Ignoring directory: {}", path.display());
-                continue;
+    let files = file_filter.collect_rust_files(root_dir)?;
+    let extractors = ExtractorTable::new(&extractors);
+    let previous_cache: &ExtractionCache = cache;
+    let file_results = files
+        .par_iter()
+        .map(|path| {
+            let code = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read file '{}'", path.display()))?;
+            // Convert the relative path to an absolute path
+            let absolute_path = path
+                .canonicalize()
+                .with_context(|| format!("Failed to canonicalize path: {}", path.display()))?;
+            let file_path = absolute_path.display().to_string();
+            let content_hash = content_hash(&code);
+            let crate_context = crate_index.context_for(&absolute_path);
+
+            if let Some(data) = previous_cache.lookup(&file_path, &content_hash, &crate_context) {
+                println!("Reusing cached file: {}", path.display());
+                let entry = CacheEntry {
+                    content_hash,
+                    crate_context,
+                    data: data.clone(),
+                };
+                return Ok((file_path, entry, true));
             }
-        }
 
-        if path.is_file() {
-            if path.extension().map_or(false, |ext| ext == "rs") {
-                println!("Parsing file: {}", path.display());
-                let code = fs::read_to_string(path)
-                    .with_context(|| format!("Failed to read file '{}'", path.display()))?;
-                all_results
-                    .file_contents
-                    .insert(path.display().to_string(), code.clone());
-                let mut parser = Parser::new();
-                parser
-                    .set_language(&tree_sitter_rust::LANGUAGE.into())
-                    .context("Error loading Rust grammar")?;
-                let tree = parser.parse(&code, None);
-
-                match tree {
-                    Some(syntax_tree) => {
-                        // Convert the relative path to an absolute path
-                        let absolute_path = path.canonicalize().with_context(|| {
-                            format!("Failed to canonicalize path: {}", path.display())
-                        })?;
-                         all_results
-                            .file_contents
-                            .insert(absolute_path.display().to_string(), code.clone());
-                        let root_node = syntax_tree.root_node();
-                        let mut node_kinds: HashSet<String> = HashSet::new();
-                        traverse_tree(
-                            root_node,
-                            &code,
-                            extractors.as_slice(),
-                            absolute_path.display().to_string(),
-                            &mut all_results,
-                            &mut node_kinds,
-                        );
-                    }
-                    None => {
-                        println!("Parsing failed for file: {}", path.display());
-                    }
-                }
+            let context = ExtractContext::new(&absolute_path, crate_context.clone());
+            let data = extract_file(path, code, &context, &extractors)?;
+            let entry = CacheEntry {
+                content_hash,
+                crate_context,
+                data,
+            };
+            Ok((file_path, entry, false))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    for (file_path, entry, reused) in file_results {
+        all_results.merge(entry.data.clone());
+        cache.record(file_path, entry, reused);
+    }
+    Ok(all_results)
+}
+
+/// Parses and extracts a single file whose contents have already been read.
+pub fn extract_file(
+    path: &Path,
+    code: String,
+    context: &ExtractContext,
+    extractors: &ExtractorTable,
+) -> Result<ExtractedData> {
+    println!("Parsing file: {}", path.display());
+    let tree = with_rust_parser(|parser| Ok(parser.parse(&code, None)))?;
+    Ok(extract_parsed_file(
+        path,
+        code,
+        tree.as_ref(),
+        context,
+        extractors,
+    ))
+}
+
+/// Runs the extractors over a file that has already been parsed.
+pub fn extract_parsed_file(
+    path: &Path,
+    code: String,
+    tree: Option<&Tree>,
+    context: &ExtractContext,
+    extractors: &ExtractorTable,
+) -> ExtractedData {
+    let mut file_results = ExtractedData::default();
+
+    file_results
+        .file_contents
+        .insert(path.display().to_string(), code.clone());
+
+    match tree {
+        Some(syntax_tree) => {
+            let root_node = syntax_tree.root_node();
+            traverse_tree(root_node, &code, extractors, context, &mut file_results);
+            if let Some(file_doc) = inner_doc_comment(root_node, &code) {
+                file_results
+                    .file_docs
+                    .insert(context.file_path.clone(), file_doc);
             }
+            let file_attributes = inner_attributes(root_node, &code);
+            if !file_attributes.is_empty() {
+                file_results
+                    .file_attributes
+                    .insert(context.file_path.clone(), file_attributes);
+            }
+            file_results.diagnostics =
+                collect_syntax_diagnostics(root_node, &code, &context.file_path);
+        }
+        None => {
+            println!("Parsing failed for file: {}", path.display());
+            file_results.diagnostics =
+                vec![SyntaxDiagnostic::parse_failed(&code, &context.file_path)];
         }
     }
-    Ok(all_results)
+    mark_items_in_error_regions(&mut file_results);
+    file_results
+        .file_contents
+        .insert(context.file_path.clone(), code);
+    file_results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::{ItemMeta, QueryItemInfo};
+
+    /// Records every node it is called for as a query item of kind `label`.
+    struct Recorder {
+        label: &'static str,
+        kinds: &'static [&'static str],
+        descend: bool,
+    }
+
+    impl InfoExtractor for Recorder {
+        fn extract(
+            &self,
+            node: Node,
+            code: &str,
+            context: &ExtractContext,
+            extracted_data_: &mut ExtractedData,
+        ) -> Result<(), anyhow::Error> {
+            let name = node
+                .child_by_field_name("name")
+                .map(|name| name.utf8_text(code.as_bytes()))
+                .transpose()?
+                .unwrap_or_default()
+                .to_string();
+            extracted_data_.query_items.push(QueryItemInfo {
+                item_kind: self.label.to_string(),
+                meta: ItemMeta {
+                    qualified_path: context.qualified_path(&name),
+                    ..ItemMeta::at_node(node, context)
+                },
+                name,
+                ..Default::default()
+            });
+            Ok(())
+        }
+
+        fn node_kinds(&self) -> &'static [&'static str] {
+            self.kinds
+        }
+
+        fn descend(&self) -> bool {
+            self.descend
+        }
+    }
+
+    const CODE: &str = "mod outer {
+    fn f() {
+        fn inner() {}
+    }
+    mod nested {
+        fn deep() {}
+    }
+}
+struct S;
+enum E {}
+";
+
+    fn walk(extractors: &[&dyn InfoExtractor]) -> ExtractedData {
+        let tree = with_rust_parser(|parser| Ok(parser.parse(CODE, None)))
+            .unwrap()
+            .unwrap();
+        let context = ExtractContext::new(
+            Path::new("/p/src/lib.rs"),
+            CrateContext {
+                crate_name: "p".to_string(),
+                ..Default::default()
+            },
+        );
+        let mut data = ExtractedData::default();
+        traverse_tree(
+            tree.root_node(),
+            CODE,
+            &ExtractorTable::new(extractors),
+            &context,
+            &mut data,
+        );
+        data
+    }
+
+    /// Qualified paths of the items `label` recorded, in walk order.
+    fn recorded<'a>(data: &'a ExtractedData, label: &str) -> Vec<&'a str> {
+        data.query_items
+            .iter()
+            .filter(|item| item.item_kind == label)
+            .map(|item| item.meta.qualified_path.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn descending_extractors_reach_nested_items() {
+        let functions = Recorder {
+            label: "functions",
+            kinds: &["function_item"],
+            descend: true,
+        };
+        let data = walk(&[&functions]);
+        assert_eq!(
+            recorded(&data, "functions"),
+            [
+                "p::outer::f",
+                "p::outer::f::inner",
+                "p::outer::nested::deep"
+            ]
+        );
+        let f = &data.query_items[0];
+        let inner = &data.query_items[1];
+        assert_eq!(inner.meta.parent_id.as_ref(), Some(&f.meta.id));
+    }
+
+    #[test]
+    fn extractors_that_do_not_descend_skip_only_their_own_subtree() {
+        let outermost = Recorder {
+            label: "outermost",
+            kinds: &["function_item"],
+            descend: false,
+        };
+        let all = Recorder {
+            label: "all",
+            kinds: &["function_item"],
+            descend: true,
+        };
+        let modules = Recorder {
+            label: "modules",
+            kinds: &["mod_item"],
+            descend: false,
+        };
+        let data = walk(&[&outermost, &all, &modules]);
+        assert_eq!(
+            recorded(&data, "outermost"),
+            ["p::outer::f", "p::outer::nested::deep"]
+        );
+        assert_eq!(recorded(&data, "all").len(), 3);
+        // `nested` is inside `outer`, which this extractor does not descend into.
+        assert_eq!(recorded(&data, "modules"), ["p::outer"]);
+    }
+
+    #[test]
+    fn one_extractor_may_register_several_kinds() {
+        let types = Recorder {
+            label: "types",
+            kinds: &["struct_item", "enum_item"],
+            descend: false,
+        };
+        let functions = Recorder {
+            label: "functions",
+            kinds: &["function_item"],
+            descend: true,
+        };
+        let extractors: [&dyn InfoExtractor; 2] = [&types, &functions];
+        let mut kinds: Vec<_> = ExtractorTable::new(&extractors).kinds().collect();
+        kinds.sort();
+        assert_eq!(kinds, ["enum_item", "function_item", "struct_item"]);
+
+        let data = walk(&extractors);
+        assert_eq!(recorded(&data, "types"), ["p::S", "p::E"]);
+    }
 }
@@ -1,3 +1,8 @@
 pub(crate) mod print_blocks;
 pub(crate) mod print_children;
+pub(crate) mod print_diagnostics;
 pub(crate) mod print_extracted_stats;
+pub(crate) mod print_node_census;
+pub(crate) mod print_orphan_files;
+pub(crate) mod print_query_comparison;
+pub(crate) mod show_items;
@@ -0,0 +1,12 @@
+use crate::diagnostics::SyntaxDiagnostic;
+
+/// Prints each diagnostic compiler-style: location, message and the source line.
+pub fn print_diagnostics(diagnostics: &[SyntaxDiagnostic]) {
+    for diagnostic in diagnostics {
+        eprintln!(
+            "{}:{}:{}: syntax error: {}",
+            diagnostic.file_path, diagnostic.line, diagnostic.column, diagnostic.message
+        );
+        eprintln!("    {}", diagnostic.source_line);
+    }
+}
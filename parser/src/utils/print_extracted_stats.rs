@@ -1,8 +1,8 @@
 use crate::extract::ExtractedData;
 use prettytable::{row, Table};
-use std::path::Path;
+use std::collections::BTreeMap;
 
-pub fn print_extracted_stats(extracted: &ExtractedData, output_file_path: &Path) {
+pub fn print_extracted_stats(extracted: &ExtractedData) {
     let mut table = Table::new();
 
     table.add_row(row!["Category", "Count"]);
@@ -21,6 +21,7 @@ pub fn print_extracted_stats(extracted: &ExtractedData, output_file_path: &Path)
     table.add_row(row!["Impls", extracted.impls.len()]);
 
     table.add_row(row!["Use Dependencies", extracted.use_dependencies.len()]);
+    table.add_row(row!["Imports", extracted.imports.len()]);
 
     table.add_row(row!["Mods", extracted.mods.len()]);
     table.add_row(row!["Enums", extracted.enums.len()]);
@@ -31,7 +32,41 @@ pub fn print_extracted_stats(extracted: &ExtractedData, output_file_path: &Path)
     }
     table.add_row(row!["Enum Variants", total_variants]);
     table.add_row(row!["Macros", extracted.macros.len()]);
+    let linked_macros = extracted
+        .macros
+        .iter()
+        .filter(|macro_info| macro_info.definition_id.is_some())
+        .count();
+    table.add_row(row!["Linked Macro Invocations", linked_macros]);
+    table.add_row(row!["Macro Definitions", extracted.macro_definitions.len()]);
+    table.add_row(row!["Traits", extracted.traits.len()]);
+    table.add_row(row!["Consts", extracted.consts.len()]);
+    table.add_row(row!["Statics", extracted.statics.len()]);
+    table.add_row(row!["Unions", extracted.unions.len()]);
+    table.add_row(row!["Extern Crates", extracted.extern_crates.len()]);
+    table.add_row(row!["Foreign Blocks", extracted.foreign_mods.len()]);
+    let foreign_items: usize = extracted
+        .foreign_mods
+        .iter()
+        .map(|foreign_mod| foreign_mod.functions.len() + foreign_mod.statics.len())
+        .sum();
+    table.add_row(row!["Foreign Items", foreign_items]);
+    table.add_row(row!["Files With Docs", extracted.file_docs.len()]);
+    table.add_row(row![
+        "Files With Inner Attributes",
+        extracted.file_attributes.len()
+    ]);
+    table.add_row(row!["Module Trees", extracted.module_trees.len()]);
+    table.add_row(row!["Orphan Files", extracted.orphan_files.len()]);
+    table.add_row(row!["Syntax Diagnostics", extracted.diagnostics.len()]);
+
+    let mut query_counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for item in &extracted.query_items {
+        *query_counts.entry(&item.item_kind).or_default() += 1;
+    }
+    for (item_kind, count) in query_counts {
+        table.add_row(row![format!("Query: {}", item_kind), count]);
+    }
 
-    println!("Extracted data saved to {}", output_file_path.display());
     table.printstd();
 }
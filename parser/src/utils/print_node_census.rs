@@ -0,0 +1,54 @@
+use crate::traverse::NodeKindCensus;
+use prettytable::{row, Table};
+
+/// Prints the node kind totals, most common first. Kinds no extractor is
+/// registered for are marked so missing extractors stand out.
+pub fn print_node_census(census: &NodeKindCensus, by_file: bool, by_parent: bool) {
+    let mut totals: Vec<(&String, &usize)> = census.totals.iter().collect();
+    totals.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+    let mut table = Table::new();
+    table.add_row(row!["Node Kind", "Count", "Files", "Extractor"]);
+    for (kind, count) in &totals {
+        let files = census
+            .per_file
+            .values()
+            .filter(|counts| counts.contains_key(*kind))
+            .count();
+        let handled = if census.is_handled(kind) { "yes" } else { "-" };
+        table.add_row(row![kind, count, files, handled]);
+    }
+    table.printstd();
+
+    let unhandled = totals
+        .iter()
+        .filter(|(kind, _)| !census.is_handled(kind))
+        .count();
+    println!(
+        "{} node kinds, {} without an extractor",
+        totals.len(),
+        unhandled
+    );
+
+    if by_file {
+        let mut table = Table::new();
+        table.add_row(row!["File", "Node Kind", "Count"]);
+        for (file_path, counts) in &census.per_file {
+            for (kind, count) in counts {
+                table.add_row(row![file_path, kind, count]);
+            }
+        }
+        table.printstd();
+    }
+
+    if by_parent {
+        let mut table = Table::new();
+        table.add_row(row!["Parent Kind", "Child Kind", "Count"]);
+        for (parent, children) in &census.per_parent {
+            for (kind, count) in children {
+                table.add_row(row![parent, kind, count]);
+            }
+        }
+        table.printstd();
+    }
+}
@@ -0,0 +1,6 @@
+/// Warns about indexed files that no crate reaches through `mod` declarations.
+pub fn print_orphan_files(orphan_files: &[String]) {
+    for file_path in orphan_files {
+        eprintln!("{}: not reached by any `mod` declaration", file_path);
+    }
+}
@@ -0,0 +1,65 @@
+use crate::query_extract::{ItemKey, QueryComparison};
+use prettytable::{row, Table};
+
+/// Prints how many items each side found per kind, then lists every item only
+/// one side found, grouped by file.
+pub fn print_query_comparison(comparisons: &[QueryComparison]) {
+    let mut table = Table::new();
+    table.add_row(row![
+        "Item Kind",
+        "Hand-written",
+        "Query",
+        "Only Hand-written",
+        "Only Query"
+    ]);
+    for comparison in comparisons {
+        table.add_row(row![
+            comparison.item_kind,
+            comparison.handwritten_count,
+            comparison.query_count,
+            comparison.only_handwritten.len(),
+            comparison.only_query.len()
+        ]);
+    }
+    table.printstd();
+
+    for comparison in comparisons {
+        print_mismatches(
+            &comparison.item_kind,
+            "hand-written",
+            &comparison.only_handwritten,
+        );
+        print_mismatches(&comparison.item_kind, "query", &comparison.only_query);
+    }
+
+    let mismatched = comparisons
+        .iter()
+        .filter(|c| !c.only_handwritten.is_empty() || !c.only_query.is_empty())
+        .count();
+    println!(
+        "{} item kinds compared, {} with mismatches",
+        comparisons.len(),
+        mismatched
+    );
+}
+
+fn print_mismatches(item_kind: &str, side: &str, keys: &[ItemKey]) {
+    if keys.is_empty() {
+        return;
+    }
+    println!(
+        "--- {} items only found by the {} extractor ---",
+        item_kind, side
+    );
+    let mut current_file = None;
+    for key in keys {
+        if current_file != Some(&key.file_path) {
+            println!("{}", key.file_path);
+            current_file = Some(&key.file_path);
+        }
+        println!(
+            "    {}-{} {}",
+            key.start_position, key.end_position, key.name
+        );
+    }
+}
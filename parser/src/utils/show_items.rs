@@ -0,0 +1,31 @@
+use crate::extract::ExtractedData;
+
+/// Prints the source text of every extracted item whose name matches `name`.
+/// Returns the number of items printed.
+pub fn show_items(extracted: &ExtractedData, name: &str) -> usize {
+    let matches: Vec<_> = extracted
+        .items()
+        .filter(|item| item.name() == name || item.alias() == Some(name))
+        .collect();
+
+    for item in &matches {
+        let meta = item.meta();
+        println!(
+            "--- {} {} ({}:{}-{}) ---",
+            item.kind_label(),
+            name,
+            meta.file_path,
+            meta.start_position,
+            meta.end_position
+        );
+        match extracted
+            .file_contents
+            .get(&meta.file_path)
+            .and_then(|code| code.get(meta.start_position..meta.end_position))
+        {
+            Some(source) => println!("{}", source),
+            None => println!("<source not available>"),
+        }
+    }
+    matches.len()
+}
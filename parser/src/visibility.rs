@@ -0,0 +1,329 @@
+// src/visibility.rs
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use tree_sitter::Node;
+
+use crate::crates::CrateContext;
+use crate::extract::ExtractedData;
+
+/// Where an item may be named from, as written on the item.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Visibility {
+    /// No modifier, or `pub(self)`: the enclosing module only
+    #[default]
+    Private,
+    /// `pub(super)`: the parent module
+    Super,
+    /// `pub(in path)`: the module at `path`, e.g. `crate::ui`
+    InPath(String),
+    /// `pub(crate)`, or the old bare `crate`: anywhere in the crate
+    Crate,
+    /// `pub`: anywhere the enclosing modules allow
+    Public,
+}
+
+impl Visibility {
+    /// Parses the text of a `visibility_modifier`, e.g. `pub(in crate::ui)`.
+    pub fn parse(text: &str) -> Self {
+        let text: String = text.split_whitespace().collect();
+        match text.as_str() {
+            "" | "pub(self)" => Visibility::Private,
+            "pub" => Visibility::Public,
+            "pub(crate)" | "crate" => Visibility::Crate,
+            "pub(super)" => Visibility::Super,
+            _ => match text
+                .strip_prefix("pub(in")
+                .and_then(|path| path.strip_suffix(')'))
+            {
+                Some(path) => Visibility::InPath(path.to_string()),
+                None => Visibility::Private,
+            },
+        }
+    }
+
+    /// The visibility written on `node`, which is private without a
+    /// `visibility_modifier` child.
+    pub fn of_node(node: Node, code: &str) -> Self {
+        let mut cursor = node.walk();
+        let modifier = node
+            .children(&mut cursor)
+            .find(|child| child.kind() == "visibility_modifier");
+        modifier
+            .and_then(|modifier| modifier.utf8_text(code.as_bytes()).ok())
+            .map(Visibility::parse)
+            .unwrap_or_default()
+    }
+
+    /// Rough order from least to most visible. `pub(in path)` and
+    /// `pub(super)` depend on where they are written, so they are only
+    /// ranked against the other kinds; `narrowed_to` compares two paths.
+    fn rank(&self) -> u8 {
+        match self {
+            Visibility::Private => 0,
+            Visibility::Super => 1,
+            Visibility::InPath(_) => 2,
+            Visibility::Crate => 3,
+            Visibility::Public => 4,
+        }
+    }
+
+    /// The more restrictive of `self` and `other`. Of two `pub(in path)`,
+    /// the one naming a module inside the other's is narrower; paths that do
+    /// not nest, such as `super` and `crate::ui`, keep `self`.
+    pub fn narrowed_to(self, other: &Visibility) -> Visibility {
+        match (&self, other) {
+            (Visibility::InPath(own), Visibility::InPath(theirs)) => {
+                if is_nested_in(theirs, own) {
+                    other.clone()
+                } else {
+                    self
+                }
+            }
+            _ if other.rank() < self.rank() => other.clone(),
+            _ => self,
+        }
+    }
+}
+
+/// Whether the module path `inner` names a module strictly inside `outer`,
+/// e.g. `crate::ui::menu` inside `crate::ui`.
+fn is_nested_in(inner: &str, outer: &str) -> bool {
+    let inner: Vec<&str> = inner.split("::").collect();
+    let outer: Vec<&str> = outer.split("::").collect();
+    inner.len() > outer.len() && inner.starts_with(&outer)
+}
+
+/// Where the items of a module can be named from, given the module's
+/// visibility and path: a private module is visible in the module it is
+/// declared in, and a `pub(super)` one in that module's parent. Modules at
+/// the crate root, or their parent, are visible in the whole crate.
+fn module_reach(visibility: &Visibility, qualified_path: &str) -> Visibility {
+    // The first segment is the crate name, or `crate`.
+    let segments: Vec<&str> = qualified_path.split("::").collect();
+    let levels_up = match visibility {
+        Visibility::Private => 1,
+        Visibility::Super => 2,
+        _ => return visibility.clone(),
+    };
+    let visible_in = &segments[1..segments.len().saturating_sub(levels_up).max(1)];
+    if visible_in.is_empty() {
+        Visibility::Crate
+    } else {
+        Visibility::InPath(format!("crate::{}", visible_in.join("::")))
+    }
+}
+
+/// What an item's parent id points at, as far as visibility is concerned.
+enum Parent {
+    Module {
+        /// Where the module's items can be named from, see `module_reach`
+        reach: Visibility,
+        parent_id: Option<String>,
+    },
+    Trait {
+        visibility: Visibility,
+        parent_id: Option<String>,
+    },
+    Impl {
+        is_trait_impl: bool,
+        parent_id: Option<String>,
+    },
+    Function,
+}
+
+/// Computes `effective_visibility` for every item of `data`: its own
+/// visibility narrowed by every module, inline or in its own file, between
+/// the item and the crate root. An effectively `Public` item of a library is
+/// reachable from other crates.
+///
+/// Call once after `resolve_module_tree`, which makes the `mod` declaration
+/// of a file the parent of the file's items. Files no crate root reaches fall
+/// back to the modules of where they sit on disk. Re-exports through
+/// `pub use` are not followed.
+/// Items of trait impls are as visible as the impl, items of traits as
+/// visible as the trait, and items inside function bodies are private.
+pub fn resolve_effective_visibility(data: &mut ExtractedData) {
+    let mut parents: HashMap<String, Parent> = HashMap::new();
+    // Modules by crate root and qualified path, to find the `mod`
+    // declarations of a file's module path.
+    let mut modules: HashMap<(String, String), Visibility> = HashMap::new();
+    for item in &data.mods {
+        let reach = module_reach(&item.meta.visibility, &item.meta.qualified_path);
+        let parent = Parent::Module {
+            reach: reach.clone(),
+            parent_id: item.meta.parent_id.clone(),
+        };
+        parents.insert(item.meta.id.clone(), parent);
+        let key = (
+            item.meta.crate_context.crate_root.clone(),
+            item.meta.qualified_path.clone(),
+        );
+        modules.insert(key, reach);
+    }
+    let resolved_files: HashSet<String> = data
+        .module_trees
+        .iter()
+        .flat_map(|tree| tree.file_paths())
+        .map(|file_path| file_path.to_string())
+        .collect();
+    for item in &data.traits {
+        let parent = Parent::Trait {
+            visibility: item.meta.visibility.clone(),
+            parent_id: item.meta.parent_id.clone(),
+        };
+        parents.insert(item.meta.id.clone(), parent);
+    }
+    for item in &data.impls {
+        let parent = Parent::Impl {
+            is_trait_impl: item.trait_name.is_some(),
+            parent_id: item.meta.parent_id.clone(),
+        };
+        parents.insert(item.meta.id.clone(), parent);
+    }
+    for item in &data.functions {
+        parents.insert(item.meta.id.clone(), Parent::Function);
+    }
+
+    let effective = |visibility: &Visibility,
+                     parent_id: Option<&str>,
+                     crate_context: &CrateContext,
+                     file_path: &str| {
+        let mut effective = visibility.clone();
+        let mut parent_id = parent_id.map(str::to_string);
+        while let Some(id) = parent_id {
+            parent_id = match parents.get(&id) {
+                Some(Parent::Module { reach, parent_id }) => {
+                    effective = effective.narrowed_to(reach);
+                    parent_id.clone()
+                }
+                Some(Parent::Trait {
+                    visibility,
+                    parent_id,
+                }) => {
+                    effective = visibility.clone();
+                    parent_id.clone()
+                }
+                Some(Parent::Impl {
+                    is_trait_impl,
+                    parent_id,
+                }) => {
+                    if *is_trait_impl {
+                        effective = Visibility::Public;
+                    }
+                    parent_id.clone()
+                }
+                Some(Parent::Function) => return Visibility::Private,
+                None => None,
+            };
+        }
+        if resolved_files.contains(file_path) {
+            return effective;
+        }
+        // The modules the file itself is nested in, innermost first.
+        let crate_prefix = match crate_context.crate_name.as_str() {
+            "" => "crate",
+            crate_name => crate_name,
+        };
+        let mut module_path = crate_context.module_path(Path::new(file_path));
+        while !module_path.is_empty() {
+            let path = format!("{}::{}", crate_prefix, module_path.join("::"));
+            if let Some(visibility) = modules.get(&(crate_context.crate_root.clone(), path)) {
+                effective = effective.narrowed_to(visibility);
+            }
+            module_path.pop();
+        }
+        effective
+    };
+
+    for item in data.items_mut() {
+        if !item.has_visibility() {
+            continue;
+        }
+        let meta = item.meta_mut();
+        meta.effective_visibility = effective(
+            &meta.visibility,
+            meta.parent_id.as_deref(),
+            &meta.crate_context,
+            &meta.file_path,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_restricted_visibilities() {
+        assert_eq!(Visibility::parse("pub(crate)"), Visibility::Crate);
+        assert_eq!(Visibility::parse("pub(super)"), Visibility::Super);
+        assert_eq!(Visibility::parse("pub(self)"), Visibility::Private);
+        assert_eq!(
+            Visibility::parse("pub(in crate::ui)"),
+            Visibility::InPath("crate::ui".to_string())
+        );
+        assert_eq!(
+            Visibility::parse("pub(in super::super)"),
+            Visibility::InPath("super::super".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_ignores_whitespace() {
+        assert_eq!(Visibility::parse("pub ( crate )"), Visibility::Crate);
+        assert_eq!(
+            Visibility::parse("pub(in  crate :: ui)"),
+            Visibility::InPath("crate::ui".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_plain_and_unknown_visibilities() {
+        assert_eq!(Visibility::parse("pub"), Visibility::Public);
+        assert_eq!(Visibility::parse("crate"), Visibility::Crate);
+        assert_eq!(Visibility::parse(""), Visibility::Private);
+        assert_eq!(Visibility::parse("pub(nonsense)"), Visibility::Private);
+    }
+
+    #[test]
+    fn narrowing_keeps_the_more_restrictive_visibility() {
+        assert_eq!(
+            Visibility::Public.narrowed_to(&Visibility::Crate),
+            Visibility::Crate
+        );
+        assert_eq!(
+            Visibility::Private.narrowed_to(&Visibility::Public),
+            Visibility::Private
+        );
+    }
+
+    #[test]
+    fn narrowing_between_paths_keeps_the_innermost_module() {
+        let in_path = |path: &str| Visibility::InPath(path.to_string());
+        assert_eq!(
+            in_path("crate::ui").narrowed_to(&in_path("crate::ui::menu")),
+            in_path("crate::ui::menu")
+        );
+        assert_eq!(
+            in_path("crate::ui::menu").narrowed_to(&in_path("crate::ui")),
+            in_path("crate::ui::menu")
+        );
+        // `crate::uix` is not inside `crate::ui`.
+        assert_eq!(
+            in_path("crate::ui").narrowed_to(&in_path("crate::uix")),
+            in_path("crate::ui")
+        );
+        assert_eq!(
+            in_path("super").narrowed_to(&in_path("crate::ui")),
+            in_path("super")
+        );
+        assert_eq!(
+            in_path("crate::ui").narrowed_to(&Visibility::Crate),
+            in_path("crate::ui")
+        );
+    }
+}
@@ -0,0 +1,498 @@
+// src/watch.rs
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs, io,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode};
+use tree_sitter::{InputEdit, Parser, Point, Tree};
+
+use crate::config::Config;
+use crate::crates::CrateIndex;
+use crate::extract::ExtractedData;
+use crate::file_filter::FileFilter;
+use crate::modules::resolve_module_tree;
+use crate::saver::save_output;
+use crate::traverse::{extract_parsed_file, ExtractContext, ExtractorTable, InfoExtractor};
+use crate::utils::print_diagnostics::print_diagnostics;
+use crate::visibility::resolve_effective_visibility;
+
+const MANIFEST_FILE: &str = "Cargo.toml";
+
+/// A file of the target kept in memory between change events.
+struct WatchedFile {
+    code: String,
+    tree: Option<Tree>,
+    data: ExtractedData,
+}
+
+/// What re-reading one file did to the watched files.
+#[derive(Debug, PartialEq, Eq)]
+enum FileUpdate {
+    Unchanged,
+    Updated,
+    /// The file was gone by the time it was read
+    Removed,
+}
+
+/// Keeps the extracted data of the configured roots up to date with the file system.
+pub struct IndexWatcher<'a> {
+    config: &'a Config,
+    file_filter: FileFilter,
+    extractors: ExtractorTable<'a>,
+    parser: Parser,
+    // One per configured root, in the same order.
+    crate_indexes: Vec<CrateIndex>,
+    // Keyed by canonical path so change events and walker paths line up.
+    files: BTreeMap<PathBuf, WatchedFile>,
+}
+
+impl<'a> IndexWatcher<'a> {
+    pub fn new(config: &'a Config, extractors: Vec<&'a dyn InfoExtractor>) -> Result<Self> {
+        let mut parser = Parser::new();
+        parser
+            .set_language(&tree_sitter_rust::LANGUAGE.into())
+            .context("Error loading Rust grammar")?;
+        let mut watcher = IndexWatcher {
+            config,
+            file_filter: FileFilter::from_config(config)?,
+            extractors: ExtractorTable::new(&extractors),
+            parser,
+            crate_indexes: Vec::new(),
+            files: BTreeMap::new(),
+        };
+        watcher.discover_crates()?;
+        Ok(watcher)
+    }
+
+    fn discover_crates(&mut self) -> Result<()> {
+        self.crate_indexes = self
+            .config
+            .roots
+            .iter()
+            .map(|root| CrateIndex::discover(root))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(())
+    }
+
+    /// Brings the in-memory files in line with the roots on disk. New files and
+    /// files in `changed` are parsed again, files that no longer exist (or are
+    /// no longer selected) are dropped. Returns the number of updated and
+    /// removed files.
+    ///
+    /// A file that cannot be read is reported and keeps its previous
+    /// contents. A manifest that cannot be read keeps the previous crates.
+    pub fn sync(&mut self, changed: &BTreeSet<PathBuf>) -> Result<(usize, usize)> {
+        let manifest_changed = changed
+            .iter()
+            .any(|path| path.file_name().is_some_and(|name| name == MANIFEST_FILE));
+        if manifest_changed {
+            println!("Cargo manifest changed, re-reading crates");
+            if let Err(e) = self.discover_crates() {
+                eprintln!(
+                    "Failed to re-read crates, keeping the previous ones: {:#}",
+                    e
+                );
+            }
+        }
+
+        let mut on_disk: BTreeMap<PathBuf, (PathBuf, usize)> = BTreeMap::new();
+        for (root_index, root) in self.config.roots.iter().enumerate() {
+            for walk_path in self.file_filter.collect_rust_files(root)? {
+                if let Ok(absolute_path) = walk_path.canonicalize() {
+                    on_disk.insert(absolute_path, (walk_path, root_index));
+                }
+            }
+        }
+
+        let removed_paths: Vec<PathBuf> = self
+            .files
+            .keys()
+            .filter(|path| !on_disk.contains_key(*path))
+            .cloned()
+            .collect();
+        for path in &removed_paths {
+            println!("Removing file: {}", path.display());
+            self.files.remove(path);
+        }
+
+        let (mut updated, mut removed) = (0, removed_paths.len());
+        for (absolute_path, (walk_path, root_index)) in on_disk {
+            let is_new = !self.files.contains_key(&absolute_path);
+            let needs_update = is_new || manifest_changed || changed.contains(&absolute_path);
+            if !needs_update {
+                continue;
+            }
+            match self.update_file(&absolute_path, &walk_path, root_index, manifest_changed) {
+                FileUpdate::Unchanged => {}
+                FileUpdate::Updated => updated += 1,
+                FileUpdate::Removed => removed += 1,
+            }
+        }
+        Ok((updated, removed))
+    }
+
+    /// Re-reads one file and re-extracts it, reusing the previous tree through
+    /// `Tree::edit` when the file was seen before.
+    fn update_file(
+        &mut self,
+        absolute_path: &Path,
+        walk_path: &Path,
+        root_index: usize,
+        force: bool,
+    ) -> FileUpdate {
+        let code = match fs::read_to_string(walk_path) {
+            Ok(code) => code,
+            // Deleted or renamed between the change event and the read.
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if self.files.remove(absolute_path).is_none() {
+                    return FileUpdate::Unchanged;
+                }
+                println!("Removing file: {}", absolute_path.display());
+                return FileUpdate::Removed;
+            }
+            Err(e) => {
+                eprintln!("Skipping file '{}': {}", walk_path.display(), e);
+                return FileUpdate::Unchanged;
+            }
+        };
+
+        let mut old_tree = None;
+        if let Some(previous) = self.files.get_mut(absolute_path) {
+            if previous.code == code && !force {
+                return FileUpdate::Unchanged;
+            }
+            if let (Some(mut tree), Some(edit)) =
+                (previous.tree.take(), compute_edit(&previous.code, &code))
+            {
+                tree.edit(&edit);
+                old_tree = Some(tree);
+            }
+        }
+
+        println!("Parsing file: {}", walk_path.display());
+        let tree = self.parser.parse(&code, old_tree.as_ref());
+        let context = ExtractContext::new(
+            absolute_path,
+            self.crate_indexes[root_index].context_for(absolute_path),
+        );
+        let data = extract_parsed_file(
+            walk_path,
+            code.clone(),
+            tree.as_ref(),
+            &context,
+            &self.extractors,
+        );
+        // In strict mode `save` reports the errors of every file instead.
+        if !self.config.strict {
+            print_diagnostics(&data.diagnostics);
+        }
+        self.files.insert(
+            absolute_path.to_path_buf(),
+            WatchedFile { code, tree, data },
+        );
+        FileUpdate::Updated
+    }
+
+    /// Merges the per-file results in path order, the same way a full index does.
+    pub fn extracted_data(&self) -> ExtractedData {
+        let mut extracted_data = ExtractedData::default();
+        for crate_index in &self.crate_indexes {
+            extracted_data.merge(ExtractedData {
+                crates: crate_index.crates().cloned().collect(),
+                ..Default::default()
+            });
+        }
+        for watched_file in self.files.values() {
+            extracted_data.merge(watched_file.data.clone());
+        }
+        extracted_data.link_macro_invocations();
+        resolve_module_tree(&mut extracted_data);
+        resolve_effective_visibility(&mut extracted_data);
+        extracted_data.config = self.config.clone();
+        extracted_data
+    }
+
+    /// Saves the merged data, unless strict mode is on and a watched file has
+    /// syntax errors. Unlike `index --strict` this does not fail, so watching
+    /// continues and the output is saved once the errors are fixed.
+    pub fn save(&self) -> Result<()> {
+        let extracted_data = self.extracted_data();
+        if self.config.strict && !extracted_data.diagnostics.is_empty() {
+            print_diagnostics(&extracted_data.diagnostics);
+            eprintln!(
+                "{} syntax errors found, not saving output (strict mode)",
+                extracted_data.diagnostics.len()
+            );
+            return Ok(());
+        }
+        save_output(&extracted_data, self.config)?;
+        println!(
+            "Extracted data saved to {}",
+            self.config.output.path.display()
+        );
+        Ok(())
+    }
+
+    pub fn file_count(&self) -> usize {
+        self.files.len()
+    }
+}
+
+/// Indexes the configured roots, then re-indexes touched files whenever the
+/// file system changes until the process is stopped.
+///
+/// Errors while setting up end the watch. Once it runs, a change that cannot
+/// be indexed or saved is reported and the next change is waited for.
+pub fn watch(
+    config: &Config,
+    extractors: Vec<&dyn InfoExtractor>,
+    debounce: Duration,
+) -> Result<()> {
+    let mut index_watcher = IndexWatcher::new(config, extractors)?;
+    index_watcher.sync(&BTreeSet::new())?;
+    index_watcher.save()?;
+    println!("Indexed {} files", index_watcher.file_count());
+
+    let (tx, rx) = mpsc::channel();
+    let mut debouncer = new_debouncer(debounce, tx)?;
+    for root in &config.roots {
+        debouncer
+            .watcher()
+            .watch(root, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch '{}'", root.display()))?;
+        println!("Watching {}", root.display());
+    }
+
+    for result in rx {
+        let events = match result {
+            Ok(events) => events,
+            Err(e) => {
+                eprintln!("Watch error: {}", e);
+                continue;
+            }
+        };
+        let changed: BTreeSet<PathBuf> = events
+            .into_iter()
+            .map(|event| event.path.canonicalize().unwrap_or(event.path))
+            .collect();
+        let (updated, removed) = match index_watcher.sync(&changed) {
+            Ok(counts) => counts,
+            Err(e) => {
+                eprintln!("Failed to update the index: {:#}", e);
+                continue;
+            }
+        };
+        // Writing the output can itself trigger an event when it lives inside a
+        // watched root, so only save when something actually changed.
+        if updated > 0 || removed > 0 {
+            println!("Updated {} files, removed {} files", updated, removed);
+            if let Err(e) = index_watcher.save() {
+                eprintln!(
+                    "Failed to save output to '{}': {:#}",
+                    config.output.path.display(),
+                    e
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Describes the change from `old` to `new` as a single edited byte range,
+/// found by trimming the common prefix and suffix.
+fn compute_edit(old: &str, new: &str) -> Option<InputEdit> {
+    if old == new {
+        return None;
+    }
+    let (old_bytes, new_bytes) = (old.as_bytes(), new.as_bytes());
+    let prefix = old_bytes
+        .iter()
+        .zip(new_bytes)
+        .take_while(|(a, b)| a == b)
+        .count();
+    let max_suffix = old_bytes.len().min(new_bytes.len()) - prefix;
+    let suffix = old_bytes
+        .iter()
+        .rev()
+        .zip(new_bytes.iter().rev())
+        .take(max_suffix)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let old_end_byte = old_bytes.len() - suffix;
+    let new_end_byte = new_bytes.len() - suffix;
+    Some(InputEdit {
+        start_byte: prefix,
+        old_end_byte,
+        new_end_byte,
+        start_position: point_at(old_bytes, prefix),
+        old_end_position: point_at(old_bytes, old_end_byte),
+        new_end_position: point_at(new_bytes, new_end_byte),
+    })
+}
+
+fn point_at(text: &[u8], byte: usize) -> Point {
+    let before = &text[..byte];
+    let row = before.iter().filter(|&&b| b == b'\n').count();
+    let line_start = before
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |newline| newline + 1);
+    Point {
+        row,
+        column: byte - line_start,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::extract::ModInfoExtractor;
+
+    fn watch_dir(dir: &Path, test: impl FnOnce(&mut IndexWatcher)) {
+        let config = Config {
+            roots: vec![dir.to_path_buf()],
+            ..Config::default()
+        };
+        let extractor = ModInfoExtractor {};
+        let mut index_watcher = IndexWatcher::new(&config, vec![&extractor]).unwrap();
+        index_watcher.sync(&BTreeSet::new()).unwrap();
+        test(&mut index_watcher);
+    }
+
+    #[test]
+    fn files_gone_before_they_are_read_count_as_removed() {
+        let dir = tempfile::tempdir().unwrap();
+        let file_path = dir.path().canonicalize().unwrap().join("gone.rs");
+        fs::write(&file_path, "mod a;\n").unwrap();
+        watch_dir(dir.path(), |index_watcher| {
+            assert_eq!(index_watcher.file_count(), 1);
+            fs::remove_file(&file_path).unwrap();
+            assert_eq!(
+                index_watcher.update_file(&file_path, &file_path, 0, false),
+                FileUpdate::Removed
+            );
+            assert_eq!(index_watcher.file_count(), 0);
+        });
+    }
+
+    #[test]
+    fn unreadable_files_are_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        fs::write(root.join("kept.rs"), "mod a;\n").unwrap();
+        watch_dir(dir.path(), |index_watcher| {
+            // A directory named like a source file cannot be read as one.
+            let unreadable = root.join("dir.rs");
+            fs::create_dir(&unreadable).unwrap();
+            assert_eq!(
+                index_watcher.update_file(&unreadable, &unreadable, 0, false),
+                FileUpdate::Unchanged
+            );
+
+            // Invalid UTF-8 fails the read but leaves the previous contents.
+            let kept = root.join("kept.rs");
+            fs::write(&kept, [0xff, 0xfe]).unwrap();
+            let changed = BTreeSet::from([kept.clone()]);
+            assert_eq!(index_watcher.sync(&changed).unwrap(), (0, 0));
+            assert_eq!(index_watcher.extracted_data().mods.len(), 1);
+        });
+    }
+
+    /// Applies `edit` the way tree-sitter sees it and checks it turns `old`
+    /// into `new`.
+    fn assert_edit_matches(old: &str, new: &str, edit: &InputEdit) {
+        let rebuilt = format!(
+            "{}{}{}",
+            &old[..edit.start_byte],
+            &new[edit.start_byte..edit.new_end_byte],
+            &old[edit.old_end_byte..]
+        );
+        assert_eq!(rebuilt, new);
+    }
+
+    #[test]
+    fn unchanged_text_has_no_edit() {
+        assert!(compute_edit("fn a() {}", "fn a() {}").is_none());
+    }
+
+    #[test]
+    fn edit_at_end_keeps_only_a_prefix() {
+        let (old, new) = ("fn a() {}\n", "fn a() {}\nfn b() {}\n");
+        let edit = compute_edit(old, new).unwrap();
+        assert_eq!(edit.start_byte, 10);
+        assert_eq!(edit.old_end_byte, 10);
+        assert_eq!(edit.new_end_byte, 20);
+        assert_eq!(edit.start_position, Point { row: 1, column: 0 });
+        assert_eq!(edit.old_end_position, Point { row: 1, column: 0 });
+        assert_eq!(edit.new_end_position, Point { row: 2, column: 0 });
+        assert_edit_matches(old, new, &edit);
+    }
+
+    #[test]
+    fn edit_at_start_keeps_only_a_suffix() {
+        let (old, new) = ("fn b() {}\n", "use x;\nfn b() {}\n");
+        let edit = compute_edit(old, new).unwrap();
+        assert_eq!(edit.start_byte, 0);
+        assert_eq!(edit.old_end_byte, 0);
+        assert_eq!(edit.new_end_byte, 7);
+        assert_eq!(edit.new_end_position, Point { row: 1, column: 0 });
+        assert_edit_matches(old, new, &edit);
+    }
+
+    #[test]
+    fn edit_in_the_middle_replaces_the_changed_range() {
+        let (old, new) = ("fn a() {\n    one();\n}\n", "fn a() {\n    two();\n}\n");
+        let edit = compute_edit(old, new).unwrap();
+        assert_eq!(edit.start_byte, 13);
+        assert_eq!(edit.old_end_byte, 16);
+        assert_eq!(edit.new_end_byte, 16);
+        assert_eq!(edit.start_position, Point { row: 1, column: 4 });
+        assert_eq!(edit.old_end_position, Point { row: 1, column: 7 });
+        assert_edit_matches(old, new, &edit);
+    }
+
+    #[test]
+    fn edit_from_and_to_an_empty_file() {
+        let edit = compute_edit("", "fn a() {}").unwrap();
+        assert_eq!(
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte),
+            (0, 0, 9)
+        );
+        assert_eq!(edit.new_end_position, Point { row: 0, column: 9 });
+
+        let edit = compute_edit("fn a() {}", "").unwrap();
+        assert_eq!(
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte),
+            (0, 9, 0)
+        );
+        assert_eq!(edit.old_end_position, Point { row: 0, column: 9 });
+        assert_eq!(edit.new_end_position, Point { row: 0, column: 0 });
+    }
+
+    #[test]
+    fn multi_byte_edits_count_bytes() {
+        // "é" and "ü" are two bytes each and share their first byte, so the
+        // edit may start inside a character; tree-sitter works in bytes.
+        let (old, new) = ("let s = \"é\";\n", "let s = \"ü\";\n");
+        let edit = compute_edit(old, new).unwrap();
+        assert_eq!(edit.start_byte, 10);
+        assert_eq!(edit.old_end_byte, 11);
+        assert_eq!(edit.new_end_byte, 11);
+        assert_eq!(edit.start_position, Point { row: 0, column: 10 });
+
+        let (old, new) = ("// ab\n", "// a→b\n");
+        let edit = compute_edit(old, new).unwrap();
+        assert_eq!(
+            (edit.start_byte, edit.old_end_byte, edit.new_end_byte),
+            (4, 4, 7)
+        );
+        assert_eq!(edit.new_end_position, Point { row: 0, column: 7 });
+        assert_edit_matches(old, new, &edit);
+    }
+}